@@ -1,13 +1,38 @@
 use std::path::PathBuf;
 
 use clap::{crate_version, value_parser, Arg, ArgAction::SetTrue, Command};
-use crate::{UPPER_CLI_FILE_LIMIT, UPPER_CLI_SIZE_LIMIT, utils::utils::pretty_print_usize};
+use crate::{GOAT_URL, UPPER_CLI_FILE_LIMIT, UPPER_CLI_REPORT_FILE_LIMIT, UPPER_CLI_SIZE_LIMIT, utils::{output::OutputFormat, utils::pretty_print_usize}};
 
 pub fn build_cli() -> clap::Command {
     let upper_file_limit = pretty_print_usize(*UPPER_CLI_FILE_LIMIT);
     let upper_cli_limit = pretty_print_usize(*UPPER_CLI_SIZE_LIMIT);
     let taxon_file_or_lookup_help = format!("A file of NCBI taxonomy ID's (tips) and/or binomial names.\nEach line should contain a single entry.\nFile size is limited to {} entries.", upper_file_limit);
-    let taxon_size_help = format!("The number of results to return. Max {} currently.", upper_cli_limit);
+    let newick_file_help = format!("A file of NCBI taxonomy ID's (tips) and/or binomial names, to return the joint tree of.\nEach line should contain a single entry.\nLists of more than {} entries are fetched in batches and merged, up to {} entries.", upper_file_limit, pretty_print_usize(*UPPER_CLI_REPORT_FILE_LIMIT));
+    let taxon_size_help = format!("The number of results to return. Searches of more than {} results are paged through the API.", upper_cli_limit);
+
+    let sqlite_arg = Arg::new("sqlite")
+        .long("sqlite")
+        .value_parser(value_parser!(PathBuf))
+        .conflicts_with("output-format")
+        .help("Write the results to a SQLite database (created if needed) instead of printing them.\nResults go in a table named after the index, typed from the variable data, and a lineage table if --ranks is used.\nSearching again with the same database adds to it.");
+
+    let report_format_arg = Arg::new("output-format")
+        .long("output-format")
+        .value_parser(OutputFormat::CLI_VALUES)
+        .default_value("tsv")
+        .help("The format to print the report table in. Parquet gives a typed table, and must be redirected to a file.");
+
+    let report_file_arg = Arg::new("file")
+        .short('f')
+        .long("file")
+        .value_parser(value_parser!(PathBuf))
+        .conflicts_with("taxon")
+        .help(taxon_file_or_lookup_help.clone());
+
+    let per_taxon_arg = Arg::new("per-taxon")
+        .long("per-taxon")
+        .action(SetTrue)
+        .help("Fetch a report for each taxon, a few at a time, instead of one for all of them.\nThe reports are merged into one table, with a taxon column.");
 
     let taxon_search_and_count = |name, about| {
         Command::new(name) 
@@ -54,7 +79,7 @@ pub fn build_cli() -> clap::Command {
                     .short('e')
                     .long("expression")
                     .required(false)
-                    .help("Use an expression to filter results server-side.")
+                    .help("Use an expression to filter results server-side. Clauses can be combined with AND, OR, NOT and parentheses.\nSizes can be given with units (e.g. genome_size > 1.5G), and percentages with % (e.g. gc_percent > 40%).")
             )
             .arg(
                 Arg::new("tax-rank")
@@ -250,6 +275,13 @@ pub fn build_cli() -> clap::Command {
                     .action(SetTrue)
                     .help("Print all variables in GoaT currently, with their associated variants.\nUseful for construction of expressions.")
             )
+            .arg(
+                Arg::new("output-format")
+                    .long("output-format")
+                    .value_parser(OutputFormat::CLI_VALUES)
+                    .default_value("tsv")
+                    .help("The format to print results in. JSON and JSON lines give one record per taxon/assembly, with nested fields. Parquet gives a typed table, and must be redirected to a file.")
+            )
             .arg(
                 Arg::new("progress-bar")
                     .long("progress-bar")
@@ -319,7 +351,7 @@ pub fn build_cli() -> clap::Command {
                     .short('e')
                     .long("expression")
                     .required(false)
-                    .help("Use an expression to filter results server-side.")
+                    .help("Use an expression to filter results server-side. Clauses can be combined with AND, OR, NOT and parentheses.\nSizes can be given with units (e.g. genome_size > 1.5G), and percentages with % (e.g. gc_percent > 40%).")
             )
             .arg(
                 Arg::new("tax-rank")
@@ -417,6 +449,13 @@ pub fn build_cli() -> clap::Command {
                     .action(SetTrue)
                     .help("Print all variables in GoaT currently, with their associated variants.\nUseful for construction of expressions.")
             )
+            .arg(
+                Arg::new("output-format")
+                    .long("output-format")
+                    .value_parser(OutputFormat::CLI_VALUES)
+                    .default_value("tsv")
+                    .help("The format to print results in. JSON and JSON lines give one record per taxon/assembly, with nested fields. Parquet gives a typed table, and must be redirected to a file.")
+            )
             .arg(
                 Arg::new("progress-bar")
                     .long("progress-bar")
@@ -439,6 +478,190 @@ pub fn build_cli() -> clap::Command {
             )
     };
 
+    // reports shared by the taxon and assembly indexes.
+    let newick_report = |about: &'static str| {
+        Command::new("newick")
+            .about(about)
+            .arg(
+                Arg::new("taxon")
+                    .short('t')
+                    .long("taxon")
+                    .required_unless_present("file")
+                    .help("The taxon to return a newick of. Multiple taxa will return the joint tree."),
+            )
+            .arg(
+                Arg::new("file")
+                    .short('f')
+                    .long("file")
+                    .value_parser(value_parser!(PathBuf))
+                    .conflicts_with("taxon")
+                    .help(newick_file_help.clone()),
+            )
+            .arg(
+                Arg::new("threshold")
+                    .long("threshold")
+                    .value_parser(value_parser!(i32).range(-1..))
+                    .default_value("2000")
+                    .help("Threshold for returned number of nodes. -1 disables the parameter.")
+            )
+            .arg(
+                Arg::new("output-format")
+                    .long("output-format")
+                    .value_parser(OutputFormat::CLI_VALUES)
+                    .help("Print the nodes of the tree as a table in this format, instead of the tree.")
+            )
+            .arg(
+                Arg::new("url")
+                    .short('u')
+                    .long("url")
+                    .action(SetTrue)
+                    .help("Print report URL.")
+            )
+            .arg(
+                Arg::new("rank")
+                    .short('r')
+                    .long("rank")
+                    .default_value("species")
+                    .value_parser(["species", "genus", "family", "order"])
+                    .help("The rank of the results to return."),
+            )
+            .arg(
+                Arg::new("progress-bar")
+                    .long("progress-bar")
+                    .action(SetTrue)
+                    .help("Add a progress bar to large queries, to estimate time left.")
+            )
+            .arg(
+                Arg::new("variables")
+                    .short('v')
+                    .long("variables")
+                    .help("Annotate the tips of the tree with the values of these variables. Input a comma separated string of variables.")
+            )
+            .arg(
+                Arg::new("annotation-format")
+                    .long("annotation-format")
+                    .default_value("nhx")
+                    .value_parser(["nhx", "phyloxml", "itol"])
+                    .help("The format of the annotated tree. itol writes a dataset file per variable alongside the tree.")
+            )
+            .arg(
+                Arg::new("itol-prefix")
+                    .long("itol-prefix")
+                    .default_value("goat")
+                    .help("The prefix of the iTOL dataset files, which are named <prefix>_<variable>.txt. Include a directory to write them there instead of the current directory, e.g. itol/goat.")
+            )
+            .arg(
+                Arg::new("prune-to")
+                    .long("prune-to")
+                    .value_parser(value_parser!(PathBuf))
+                    .help("Prune the tree to the tips named in this file, one name or taxon ID per line.")
+            )
+            .arg(
+                Arg::new("collapse-monotypic")
+                    .long("collapse-monotypic")
+                    .action(SetTrue)
+                    .help("Collapse nodes with a single child into that child.")
+            )
+            .arg(
+                Arg::new("label")
+                    .long("label")
+                    .default_value("name")
+                    .value_parser(["name", "taxid"])
+                    .help("Label the nodes of the tree with their names or taxon IDs.")
+            )
+    };
+
+    let hist_report = |about: &'static str| {
+        Command::new("hist")
+            .about(about)
+            .arg(
+                Arg::new("taxon")
+                    .short('t')
+                    .long("taxon")
+                    .required_unless_present("file")
+                    .help("The taxon to return a histogram of. Multiple taxa will return the joint histogram."),
+            )
+            .arg(report_file_arg.clone())
+            .arg(per_taxon_arg.clone())
+            .arg(report_format_arg.clone())
+            .arg(
+                Arg::new("url")
+                    .short('u')
+                    .long("url")
+                    .action(SetTrue)
+                    .help("Print report URL.")
+            )
+            .arg(
+                Arg::new("no-descendents")
+                    .short('n')
+                    .long("no-descendents")
+                    .action(SetTrue)
+                    .help("If a taxon is supplied, do not return values for its descendents (i.e. a tax_name() call).")
+            )
+            .arg(
+                Arg::new("rank")
+                    .short('r')
+                    .long("rank")
+                    .default_value("species")
+                    .value_parser(["species", "genus", "family", "order"])
+                    .help("The number of results to return."),
+            )
+            .arg(
+                Arg::new("x-variable")
+                    .short('x')
+                    .long("x-variable")
+                    .required(true)
+                    .help("The name of the x variable."),
+            )
+            .arg(
+                Arg::new("size")
+                    .short('s')
+                    .long("size")
+                    .default_value("10")
+                    .value_parser(value_parser!(usize))
+                    .help("The number of category levels to return."),
+            )
+            .arg(
+                Arg::new("category")
+                    .short('c')
+                    .long("category")
+                    .help("Split the histogram by a taxon rank (e.g. phylum) or a keyword variable (e.g. assembly_level).\nThe --size most common categories are returned."),
+            )
+            .arg(
+                Arg::new("cat-to-x")
+                    .long("cat-to-x")
+                    .action(SetTrue)
+                    .requires("category")
+                    .help("Put the categories on the x axis, instead of stacking them in each bin.")
+            )
+            .arg(
+                Arg::new("x-opts")
+                    .short('o')
+                    .long("opts")
+                    .required(false)
+                    .help("The options for the variable axis. A comma separated string of options in the order:
+\t1. minimum value
+\t2. maximum value
+\t3. tick count
+\t4. scale (linear, sqrt, log10, log2, log, proportion, or ordinal)
+\t5. axis title\nE.g. ',,20' is 20 bins. '1,10,5' is start at 1, end at 10, with 5 bins."),
+            )
+            .arg(
+                Arg::new("plot")
+                    .conflicts_with("per-taxon")
+                    .long("plot")
+                    .action(SetTrue)
+                    .help("Draw the histogram in the terminal, instead of printing the table.")
+            )
+            .arg(
+                Arg::new("svg")
+                    .conflicts_with("per-taxon")
+                    .long("svg")
+                    .value_parser(value_parser!(PathBuf))
+                    .help("Also draw the histogram to this SVG file, using the scale and title from --opts.")
+            )
+    };
+
     // actually parse the matches.
     Command::new("goat-cli")
         // to fix the binary name in the help messages
@@ -448,6 +671,13 @@ pub fn build_cli() -> clap::Command {
         .arg_required_else_help(true)
         .author("Max Brown, Richard Challis, Sujai Kumar, Cibele Sotero-Caio <goat@genomehubs.org>")
         .about("Genomes on a Tree. Query metadata across the tree of life.\n\nFor a tutorial on usage, visit: https://github.com/genomehubs/goat-cli/wiki\nVisit the GoaT website here: https://goat.genomehubs.org/")
+        .arg(
+            Arg::new("api-url")
+                .long("api-url")
+                .env("GOAT_API_URL")
+                .global(true)
+                .help(format!("The GoaT API URL to query, e.g. for a private genomehubs instance.\nDefaults to {}", *GOAT_URL))
+        )
         // using a taxon index
         .subcommand(
             Command::new("taxon")
@@ -455,6 +685,7 @@ pub fn build_cli() -> clap::Command {
                     .about("Query by taxon index.")
                     .subcommand(
                         taxon_search_and_count("search", "Query metadata for any taxon across the tree of life by taxon index.")
+                            .arg(sqlite_arg.clone())
                     )
                     .subcommand(
                         taxon_search_and_count("count", "Return the count of results for any taxon across the tree of life by taxon index.")
@@ -500,6 +731,7 @@ pub fn build_cli() -> clap::Command {
                                 Arg::new("taxon")
                                     .short('t')
                                     .long("taxon")
+                                    .required_unless_present("file")
                                     .help("The taxon to return sources for. Multiple taxa will return the sources for all.")
                             )
                             .arg(
@@ -510,6 +742,9 @@ pub fn build_cli() -> clap::Command {
                                     .value_parser(["species", "genus", "family", "order"])
                                     .help("The rank of the results to return."),
                             )
+                            .arg(report_file_arg.clone())
+                            .arg(per_taxon_arg.clone())
+                            .arg(report_format_arg.clone())
                             .arg(
                                 Arg::new("url")
                                     .short('u')
@@ -519,23 +754,31 @@ pub fn build_cli() -> clap::Command {
                             )
                     )
                     .subcommand(
-                        Command::new("newick")
-                            .about("Generate a newick tree from input taxa.")
+                        Command::new("files")
+                            .about("List the files (e.g. images) held for taxa, and optionally download them.")
                             .arg(
                                 Arg::new("taxon")
                                     .short('t')
                                     .long("taxon")
-                                    // FIXME: is a file actually allowed here?
                                     .required_unless_present("file")
-                                    .help("The taxon to return a newick of. Multiple taxa will return the joint tree."),
+                                    .help("The taxon to return files for. Multiple taxa will return the files for all.")
                             )
                             .arg(
-                                Arg::new("threshold")
-                                    .long("threshold")
-                                    .value_parser(value_parser!(i32).range(-1..))
-                                    .default_value("2000")
-                                    .help("Threshold for returned number of nodes. -1 disables the parameter.")
+                                Arg::new("no-descendents")
+                                    .short('n')
+                                    .long("no-descendents")
+                                    .action(SetTrue)
+                                    .help("If a taxon is supplied, do not return files for its descendents (i.e. a tax_name() call).")
                             )
+                            .arg(
+                                Arg::new("expression")
+                                    .short('e')
+                                    .long("expression")
+                                    .help("An expression to filter the taxa, e.g. 'assembly_level = chromosome'.")
+                            )
+                            .arg(report_file_arg.clone())
+                            .arg(per_taxon_arg.clone())
+                            .arg(report_format_arg.clone())
                             .arg(
                                 Arg::new("url")
                                     .short('u')
@@ -544,30 +787,31 @@ pub fn build_cli() -> clap::Command {
                                     .help("Print report URL.")
                             )
                             .arg(
-                                Arg::new("rank")
-                                    .short('r')
-                                    .long("rank")
-                                    .default_value("species")
-                                    .value_parser(["species", "genus", "family", "order"])
-                                    .help("The rank of the results to return."),
+                                Arg::new("download-dir")
+                                    .long("download-dir")
+                                    .value_parser(value_parser!(PathBuf))
+                                    .help("Download the files to this directory. Interrupted downloads are resumed, and checksums are checked where GoaT has them.")
                             )
-                            .arg(
-                                Arg::new("progress-bar")
-                                    .long("progress-bar")
-                                    .action(SetTrue)
-                                    .help("Add a progress bar to large queries, to estimate time left.")
-                            ),
                     )
                     .subcommand(
-                        Command::new("hist")
-                            .about("Generate a histogram report from input taxa.")
+                        newick_report("Generate a newick tree from input taxa.")
+                    )
+                    .subcommand(
+                        hist_report("Generate a histogram report from input taxa.")
+                    )
+                    .subcommand(
+                        Command::new("scatter")
+                            .about("Generate a scatter (bivariate) report.")
                             .arg(
                                 Arg::new("taxon")
                                     .short('t')
                                     .long("taxon")
                                     .required_unless_present("file")
-                                    .help("The taxon to return a histogram of. Multiple taxa will return the joint histogram."),
+                                    .help("The taxon to return a scatter of. Multiple taxa will return the joint scatter."),
                             )
+                            .arg(report_file_arg.clone())
+                            .arg(per_taxon_arg.clone())
+                            .arg(report_format_arg.clone())
                             .arg(
                                 Arg::new("url")
                                     .short('u')
@@ -588,7 +832,7 @@ pub fn build_cli() -> clap::Command {
                                     .long("rank")
                                     .default_value("species")
                                     .value_parser(["species", "genus", "family", "order"])
-                                    .help("The number of results to return."),
+                                    .help("The rank of the results to return."),
                             )
                             .arg(
                                 Arg::new("x-variable")
@@ -597,6 +841,13 @@ pub fn build_cli() -> clap::Command {
                                     .required(true)
                                     .help("The name of the x variable."),
                             )
+                            .arg(
+                                Arg::new("y-variable")
+                                    .short('y')
+                                    .long("y-variable")
+                                    .required(true)
+                                    .help("The name of the y variable."),
+                            )
                             .arg(
                                 Arg::new("size")
                                     .short('s')
@@ -607,27 +858,56 @@ pub fn build_cli() -> clap::Command {
                             )
                             .arg(
                                 Arg::new("x-opts")
-                                    .short('o')
-                                    .long("opts")
+                                    .long("x-opts")
                                     .required(false)
                                     .help("The options for the variable axis. A comma separated string of options in the order:
 \t1. minimum value
 \t2. maximum value
 \t3. tick count
 \t4. scale (linear, sqrt, log10, log2, log, proportion, or ordinal)
-\t5. axis title\nE.g. ',,20' is 20 bins. '1,10,5' is start at 1, end at 10, with 5 bins."),
+\t5. axis title\nE.g. ',,20' is 20 bins. '1,10,5' is start at 1, end at 10, with 5 bins.
+"),
+                            )
+                            .arg(
+                                Arg::new("y-opts")
+                                    .long("y-opts")
+                                    .required(false)
+                                    .help("As for x options."),
+                            )
+                            .arg(
+                                Arg::new("category")
+                                    .short('c')
+                                    .long("category")
+                                    .help("A variable or rank to split the scatter by, e.g. assembly_level. Use --size for the number of categories."),
+                            )
+                            .arg(
+                                Arg::new("threshold")
+                                    .long("threshold")
+                                    .value_parser(value_parser!(i32).range(0..))
+                                    .default_value("10000")
+                                    .help("Threshold for the number of points to return alongside the binned counts.")
+                            )
+                            .arg(
+                                Arg::new("svg")
+                                    .conflicts_with("per-taxon")
+                                    .long("svg")
+                                    .value_parser(value_parser!(PathBuf))
+                                    .help("Also draw the binned scatter to this SVG file, using the scales and titles from --x-opts and --y-opts.")
                             )
                     )
                     .subcommand(
-                        Command::new("scatter")
-                            .about("Generate a scatter (bivariate) report.")
+                        Command::new("map")
+                            .about("Generate a map report: the number of taxa sampled in each country.")
                             .arg(
                                 Arg::new("taxon")
                                     .short('t')
                                     .long("taxon")
                                     .required_unless_present("file")
-                                    .help("The taxon to return a scatter of. Multiple taxa will return the joint scatter."),
+                                    .help("The taxon to return a map of. Multiple taxa will return the joint map."),
                             )
+                            .arg(report_file_arg.clone())
+                            .arg(per_taxon_arg.clone())
+                            .arg(report_format_arg.clone())
                             .arg(
                                 Arg::new("url")
                                     .short('u')
@@ -648,21 +928,19 @@ pub fn build_cli() -> clap::Command {
                                     .long("rank")
                                     .default_value("species")
                                     .value_parser(["species", "genus", "family", "order"])
-                                    .help("The rank of the results to return."),
+                                    .help("The rank of the taxa to count."),
                             )
                             .arg(
-                                Arg::new("x-variable")
-                                    .short('x')
-                                    .long("x-variable")
-                                    .required(true)
-                                    .help("The name of the x variable."),
+                                Arg::new("expression")
+                                    .short('e')
+                                    .long("expression")
+                                    .help("An expression to filter the taxa counted, e.g. 'assembly_level = chromosome'."),
                             )
                             .arg(
-                                Arg::new("y-variable")
-                                    .short('y')
-                                    .long("y-variable")
-                                    .required(true)
-                                    .help("The name of the y variable."),
+                                Arg::new("category")
+                                    .short('c')
+                                    .long("category")
+                                    .help("A variable or rank to split the counts by, e.g. assembly_level. Use --size for the number of categories."),
                             )
                             .arg(
                                 Arg::new("size")
@@ -673,24 +951,103 @@ pub fn build_cli() -> clap::Command {
                                     .help("The number of category levels to return."),
                             )
                             .arg(
-                                Arg::new("x-opts")
-                                    .long("x-opts")
-                                    .required(false)
-                                    .help("The options for the variable axis. A comma separated string of options in the order:
-\t1. minimum value
-\t2. maximum value
-\t3. tick count
-\t4. scale (linear, sqrt, log10, log2, log, proportion, or ordinal)
-\t5. axis title\nE.g. ',,20' is 20 bins. '1,10,5' is start at 1, end at 10, with 5 bins.
-"),
+                                Arg::new("geojson")
+                                    .conflicts_with("per-taxon")
+                                    .long("geojson")
+                                    .value_parser(value_parser!(PathBuf))
+                                    .help("Also write the counts to this file as GeoJSON, with a point at the centre of each country."),
                             )
+                    )
+                    .subcommand(
+                        Command::new("per-rank")
+                            .about("Generate an xPerRank report: the number of taxa at each rank matching a query.")
                             .arg(
-                                Arg::new("y-opts")
-                                    .long("y-opts")
-                                    .required(false)
-                                    .help("As for x options."),
+                                Arg::new("taxon")
+                                    .short('t')
+                                    .long("taxon")
+                                    .required_unless_present("file")
+                                    .help("The taxon to count within. Multiple taxa will return the joint counts."),
+                            )
+                            .arg(report_file_arg.clone())
+                            .arg(per_taxon_arg.clone())
+                            .arg(report_format_arg.clone())
+                            .arg(
+                                Arg::new("url")
+                                    .short('u')
+                                    .long("url")
+                                    .action(SetTrue)
+                                    .help("Print report URL.")
+                            )
+                            .arg(
+                                Arg::new("no-descendents")
+                                    .short('n')
+                                    .long("no-descendents")
+                                    .action(SetTrue)
+                                    .help("If a taxon is supplied, do not return values for its descendents (i.e. a tax_name() call).")
+                            )
+                            .arg(
+                                Arg::new("expression")
+                                    .short('e')
+                                    .long("expression")
+                                    .help("An expression to filter the taxa counted, e.g. 'assembly_level = chromosome'."),
+                            )
+                            .arg(
+                                Arg::new("ranks")
+                                    .short('r')
+                                    .long("ranks")
+                                    .default_value("species,genus,family,order")
+                                    .help("A comma separated list of ranks to count taxa at."),
+                            )
+                    )
+                    .subcommand(
+                        Command::new("arc")
+                            .about("Generate an arc report: the fraction of taxa at a rank matching one expression out of those matching another.")
+                            .arg(
+                                Arg::new("taxon")
+                                    .short('t')
+                                    .long("taxon")
+                                    .required_unless_present("file")
+                                    .help("The taxon to return an arc for. Multiple taxa will return the joint arc."),
+                            )
+                            .arg(report_file_arg.clone())
+                            .arg(per_taxon_arg.clone())
+                            .arg(report_format_arg.clone())
+                            .arg(
+                                Arg::new("url")
+                                    .short('u')
+                                    .long("url")
+                                    .action(SetTrue)
+                                    .help("Print report URL.")
+                            )
+                            .arg(
+                                Arg::new("no-descendents")
+                                    .short('n')
+                                    .long("no-descendents")
+                                    .action(SetTrue)
+                                    .help("If a taxon is supplied, do not return values for its descendents (i.e. a tax_name() call).")
+                            )
+                            .arg(
+                                Arg::new("rank")
+                                    .short('r')
+                                    .long("rank")
+                                    .default_value("family")
+                                    .value_parser(["species", "genus", "family", "order", "class", "phylum"])
+                                    .help("The rank at which to count taxa."),
+                            )
+                            .arg(
+                                Arg::new("x-expression")
+                                    .short('x')
+                                    .long("x-expression")
+                                    .required(true)
+                                    .help("An expression for the taxa to count, e.g. 'assembly_level = chromosome'."),
+                            )
+                            .arg(
+                                Arg::new("y-expression")
+                                    .short('y')
+                                    .long("y-expression")
+                                    .help("An expression for the taxa to count x out of. Defaults to all taxa at the rank."),
+                            )
                     )
-                )
             )
         .subcommand(
             Command::new("assembly")
@@ -698,6 +1055,7 @@ pub fn build_cli() -> clap::Command {
                     .about("Query by assembly index.")
                     .subcommand(
                         assembly_search_and_count("search", "Query metadata for any taxon across the tree of life by assembly index.")
+                            .arg(sqlite_arg)
                     )
                     .subcommand(
                         assembly_search_and_count("count", "Return the count of results for any taxon across the tree of life by assembly index.")
@@ -736,5 +1094,48 @@ pub fn build_cli() -> clap::Command {
                                         .help("The number of results to return."),
                                 )
                     )
+                    .subcommand(
+                        newick_report("Generate a newick tree of the taxa with assemblies.")
+                    )
+                    .subcommand(
+                        hist_report("Generate a histogram report of assemblies, e.g. contig N50 across a clade.")
+                    )
+                    .subcommand(
+                        Command::new("oxford")
+                            .about("Generate an Oxford plot report: the positions of features (e.g. BUSCO genes) shared by two assemblies.")
+                            .arg(
+                                Arg::new("assemblies")
+                                    .short('a')
+                                    .long("assemblies")
+                                    .required(true)
+                                    .help("The two assembly accessions to compare, comma separated, e.g. GCA_905220365.1,GCA_905147765.1."),
+                            )
+                            .arg(
+                                Arg::new("feature-type")
+                                    .long("feature-type")
+                                    .default_value("busco-gene")
+                                    .help("The type of feature to compare the assemblies by."),
+                            )
+                            .arg(report_format_arg.clone())
+                            .arg(
+                                Arg::new("url")
+                                    .short('u')
+                                    .long("url")
+                                    .action(SetTrue)
+                                    .help("Print report URL.")
+                            )
+                            .arg(
+                                Arg::new("x-opts")
+                                    .long("x-opts")
+                                    .required(false)
+                                    .help("The options for the first assembly's axis, as for the scatter report."),
+                            )
+                            .arg(
+                                Arg::new("y-opts")
+                                    .long("y-opts")
+                                    .required(false)
+                                    .help("As for x options, for the second assembly."),
+                            )
+                    )
             )
 }
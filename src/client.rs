@@ -6,9 +6,11 @@
 //! reference-counted.
 
 use crate::error::{Error, ErrorKind, Result};
-use reqwest::header::ACCEPT;
-use reqwest::Client;
+use reqwest::header::{ACCEPT, RANGE};
+use reqwest::{Client, StatusCode};
 use serde_json::Value;
+use std::path::{Path, PathBuf};
+use tokio::io::AsyncWriteExt;
 
 /// Shared HTTP client for the GoaT API.
 #[derive(Clone)]
@@ -53,6 +55,60 @@ impl GoatClient {
         let body = self.get_text(url, "application/json").await?;
         serde_json::from_str(&body).map_err(|e| Error::new(ErrorKind::SerdeJSON(e)))
     }
+
+    /// GET `url` and write the response body to `path`, returning
+    /// the size of the file in bytes.
+    ///
+    /// The body is written to `<path>.part` first, and moved to
+    /// `path` once complete. If a `.part` file is already there
+    /// from an interrupted download, the download resumes from
+    /// where it stopped, if the server supports ranges. Files
+    /// already at `path` are not downloaded again.
+    pub async fn download(&self, url: &str, path: &Path) -> Result<u64> {
+        if let Ok(metadata) = tokio::fs::metadata(path).await {
+            return Ok(metadata.len());
+        }
+        let part = PathBuf::from(format!("{}.part", path.display()));
+        let offset = match tokio::fs::metadata(&part).await {
+            Ok(metadata) => metadata.len(),
+            Err(_) => 0,
+        };
+
+        let client = self.inner.clone();
+        let url = url.to_owned();
+        let mut resp = again::retry(move || {
+            let request = client.get(&url);
+            match offset {
+                0 => request.send(),
+                _ => request.header(RANGE, format!("bytes={}-", offset)).send(),
+            }
+        })
+        .await?;
+
+        match resp.status() {
+            // the part file is already complete
+            StatusCode::RANGE_NOT_SATISFIABLE => {}
+            status => {
+                resp = resp.error_for_status()?;
+                // only append if the server sent the rest of the file
+                let resume = status == StatusCode::PARTIAL_CONTENT;
+                let mut file = tokio::fs::OpenOptions::new()
+                    .create(true)
+                    .write(true)
+                    .append(resume)
+                    .truncate(!resume)
+                    .open(&part)
+                    .await?;
+                while let Some(chunk) = resp.chunk().await? {
+                    file.write_all(&chunk).await?;
+                }
+                file.flush().await?;
+            }
+        }
+
+        tokio::fs::rename(&part, path).await?;
+        Ok(tokio::fs::metadata(path).await?.len())
+    }
 }
 
 impl Default for GoatClient {
@@ -5,8 +5,11 @@
 use crate::client::GoatClient;
 use crate::error::{Error, ErrorKind, Result};
 use futures::StreamExt;
+use serde_json::json;
 
 use crate::utils::cli_matches::{self, CliAction};
+use crate::utils::columnar::{Column, ColumnType, ParquetWriter};
+use crate::utils::output::{csv_field, JsonWriter, OutputFormat};
 use crate::IndexType;
 
 /// `goat-cli count` presents an identical CLI to `goat-cli search` but prints
@@ -18,9 +21,83 @@ pub async fn count(
     unique_ids: Vec<String>,
     index_type: IndexType,
 ) -> Result<Option<u64>> {
-    let (size_int, url_vector, url_vector_api) =
+    let (counts, branches) =
+        match count_per_query(matches, print_warning, unique_ids, index_type).await? {
+            Some(c) => c,
+            None => return Ok(None),
+        };
+
+    if cli {
+        if branches > 1 {
+            eprintln!(
+                "The expression was split into {} queries joined by OR, so there is a count for each, and a taxon may be counted more than once.",
+                branches
+            );
+        }
+        // print to console
+        let stdout = std::io::stdout();
+        match OutputFormat::from_matches(matches) {
+            OutputFormat::Tsv => {
+                println!("search_query\tcount");
+                for (search_query, count) in &counts {
+                    println!("{}\t{}", search_query, count);
+                }
+            }
+            OutputFormat::Csv => {
+                println!("search_query,count");
+                for (search_query, count) in &counts {
+                    println!("{},{}", csv_field(search_query), count);
+                }
+            }
+            format @ (OutputFormat::Json | OutputFormat::Jsonl) => {
+                let mut writer = JsonWriter::new(stdout, format);
+                writer.write_records(&count_records(&counts))?;
+                writer.finish()?;
+            }
+            format @ OutputFormat::Parquet => {
+                format.check_stdout()?;
+                let columns = vec![
+                    Column::record("search_query", ColumnType::Text),
+                    Column::record("count", ColumnType::Long),
+                ];
+                let mut writer = ParquetWriter::new(stdout, columns)?;
+                writer.write_records(&count_records(&counts))?;
+                writer.finish()?;
+            }
+        }
+    }
+
+    Ok(Some(counts.iter().map(|(_, count)| count).sum()))
+}
+
+/// A JSON record for each search query and its count.
+fn count_records(counts: &[(String, u64)]) -> Vec<serde_json::Value> {
+    counts
+        .iter()
+        .map(|(search_query, count)| json!({"search_query": search_query, "count": count}))
+        .collect()
+}
+
+/// Fetch the number of results for each search query, in the order
+/// the queries were given on the CLI, along with the number of queries
+/// made for each (see [`CliAction::Continue`]).
+///
+/// Used by `goat-cli search` to work out how many pages a large search
+/// will need.
+pub async fn count_per_query(
+    matches: &clap::ArgMatches,
+    print_warning: bool,
+    unique_ids: Vec<String>,
+    index_type: IndexType,
+) -> Result<Option<(Vec<(String, u64)>, usize)>> {
+    let (size_int, url_vector, url_vector_api, branches) =
         match cli_matches::process_cli_args(matches, "count", unique_ids, index_type)? {
-            CliAction::Continue { size, taxa, urls } => (size, taxa, urls),
+            CliAction::Continue {
+                size,
+                taxa,
+                urls,
+                branches,
+            } => (size, taxa, urls, branches),
             CliAction::PrintedAndExit => return Ok(None),
         };
 
@@ -46,45 +123,21 @@ pub async fn count(
             }),
     )
     .buffered(concurrent_requests)
-    .collect::<Vec<_>>();
+    .collect::<Vec<Result<(String, u64)>>>();
 
     let awaited_fetches = fetches.await;
 
-    match cli {
-        true => {
-            // print to console
-            let mut outer_count = 0;
-            println!("search_query\tcount");
-            for el in awaited_fetches {
-                let (search_query, count) = match el {
-                    Ok(e) => e,
-                    Err(e) => return Err(e),
-                };
-                println!("{}\t{}", search_query, count);
-                outer_count += count;
-            }
-            Ok(Some(outer_count))
-        }
-        false => {
-            // need
-            let mut outer_count = 0;
-            // the zip does not correspond to the awaited fetches...
-            // need to match them
-            for el in awaited_fetches {
-                let (search_query, count) = match el {
-                    Ok(e) => e,
-                    Err(e) => return Err(e),
-                };
-                if print_warning && size_int < count {
-                    eprintln!(
-                        "For search query {}, size specified ({}) was less than the number of results returned, ({}).",
-                        search_query, size_int, count
-                    );
-                }
-                outer_count += count;
-            }
-
-            Ok(Some(outer_count))
+    let mut counts = Vec::new();
+    for el in awaited_fetches {
+        let (search_query, count) = el?;
+        if print_warning && size_int < count {
+            eprintln!(
+                "For search query {}, size specified ({}) was less than the number of results returned, ({}).",
+                search_query, size_int, count
+            );
         }
+        counts.push((search_query, count));
     }
+
+    Ok(Some((counts, branches)))
 }
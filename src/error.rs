@@ -1,7 +1,10 @@
+use arrow_schema::ArrowError;
 use clap::Error as ClapError;
 use indicatif::style::TemplateError;
 use owo_colors::{OwoColorize, Stream::Stderr, Style};
+use parquet::errors::ParquetError;
 use reqwest::Error as ReqError;
+use rusqlite::Error as SqliteError;
 use serde_json::Error as SerdeJSONError;
 use std::io::Error as IOError;
 use std::{error::Error as StdError, fmt, result};
@@ -52,6 +55,10 @@ pub enum ErrorKind {
     Progress(TemplateError),
     // error in report
     Report(String),
+    // errors writing parquet
+    Parquet(ParquetError),
+    // errors writing to a SQLite database
+    Sqlite(SqliteError),
 }
 
 impl From<ClapError> for Error {
@@ -84,6 +91,24 @@ impl From<TemplateError> for Error {
     }
 }
 
+impl From<ParquetError> for Error {
+    fn from(err: ParquetError) -> Self {
+        Error::new(ErrorKind::Parquet(err))
+    }
+}
+
+impl From<ArrowError> for Error {
+    fn from(err: ArrowError) -> Self {
+        Error::new(ErrorKind::Parquet(err.into()))
+    }
+}
+
+impl From<SqliteError> for Error {
+    fn from(err: SqliteError) -> Self {
+        Error::new(ErrorKind::Sqlite(err))
+    }
+}
+
 impl StdError for Error {}
 
 impl fmt::Display for Error {
@@ -109,6 +134,8 @@ impl fmt::Display for Error {
             ErrorKind::SerdeJSON(err) => write!(f, "serialising JSON error - {}", err),
             ErrorKind::Progress(err) => write!(f, "progress bar error - {}", err),
             ErrorKind::Report(err) => write!(f, "report error - {}", err),
+            ErrorKind::Parquet(err) => write!(f, "parquet error - {}", err),
+            ErrorKind::Sqlite(err) => write!(f, "SQLite error - {}", err),
         }
     }
 }
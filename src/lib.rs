@@ -34,7 +34,8 @@ const GOAT_URL_BASE: &str = "https://goat.genomehubs.org/api/";
 const GOAT_API_VERSION: &str = "v2/";
 
 lazy_static! {
-    /// The current GoaT URL.
+    /// The current GoaT URL. Used unless `--api-url` (or
+    /// `GOAT_API_URL`) points `goat-cli` at another instance.
     pub static ref GOAT_URL: String = format!("{}{}", GOAT_URL_BASE, GOAT_API_VERSION);
     /// The taxonomy that `goat-cli` uses.
     pub static ref TAXONOMY: String = "ncbi".into();
@@ -42,10 +43,15 @@ lazy_static! {
 
 // global size limits on pinging the API
 lazy_static! {
-    /// Upper limit for the CLI arg `--size`.
+    /// Upper limit for the number of results in a single request.
+    /// Searches with a larger `--size` are paged through the API.
     pub static ref UPPER_CLI_SIZE_LIMIT: usize = 50000;
     /// Upper limit for the number of entries in the file for CLI arg `-f`.
     pub static ref UPPER_CLI_FILE_LIMIT: usize = 500;
+    /// Upper limit for the number of entries in the file for a
+    /// report, which is split into requests of at most
+    /// [`UPPER_CLI_FILE_LIMIT`] taxa.
+    pub static ref UPPER_CLI_REPORT_FILE_LIMIT: usize = 10000;
 }
 
 /// The indexes we make searches over in GoaT.
@@ -55,10 +61,11 @@ lazy_static! {
 ///
 /// Each tuple variant can store their respective
 /// [`std::collections::BTreeMap`] databases.
-#[derive(Clone, Copy, Debug)]
+#[derive(Default, Clone, Copy, Debug)]
 pub enum IndexType {
     /// Taxon search index. The historical main
     /// functionality of goat-cli went through taxon.
+    #[default]
     Taxon,
     /// Assembly search index.
     Assembly,
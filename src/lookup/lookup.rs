@@ -1,8 +1,9 @@
 use crate::error::{Error, ErrorKind, Result};
+use crate::utils::utils::goat_url;
 use crate::utils::utils::{
     lines_from_file, parse_comma_separated, some_kind_of_uppercase_first_letter,
 };
-use crate::{IndexType, GOAT_URL, TAXONOMY, UPPER_CLI_FILE_LIMIT};
+use crate::{IndexType, TAXONOMY, UPPER_CLI_FILE_LIMIT};
 use std::path::PathBuf;
 use url::Url;
 
@@ -16,13 +17,15 @@ pub struct Lookup {
     /// The index type, currently taxon or
     /// assembly
     pub index_type: IndexType,
+    /// The GoaT API URL to make the lookup against.
+    pub goat_url: String,
 }
 
 impl Lookup {
     /// From our lookup struct we can make an individual URL.
     pub fn make_url(&self) -> String {
-        let base = format!("{}lookup", *GOAT_URL);
-        let mut url = Url::parse(&base).expect("GOAT_URL is a valid base");
+        let base = format!("{}lookup", self.goat_url);
+        let mut url = Url::parse(&base).expect("goat_url is a valid base");
         url.query_pairs_mut()
             .append_pair("searchTerm", &self.search)
             .append_pair("size", &self.size.to_string())
@@ -48,6 +51,7 @@ impl Lookups {
         let filename_op = matches.get_one::<PathBuf>("file");
         // safe to unwrap, as default is defined.
         let no_hits = *matches.get_one::<u64>("size").expect("cli default = 10");
+        let goat_url = goat_url(matches)?;
 
         let tax_name_vector: Vec<String>;
         match tax_name_op {
@@ -64,9 +68,9 @@ impl Lookups {
                     }
                 }
                 None => {
-                    return Err(Error::new(ErrorKind::GenericCli(format!(
-                        "one of -f (--file) or -t (--taxon) should be specified."
-                    ))))
+                    return Err(Error::new(ErrorKind::GenericCli(
+                        "one of -f (--file) or -t (--taxon) should be specified.".to_string(),
+                    )))
                 }
             },
         }
@@ -78,6 +82,7 @@ impl Lookups {
                 search: el,
                 size: no_hits,
                 index_type,
+                goat_url: goat_url.clone(),
             })
         }
 
@@ -101,26 +106,21 @@ impl Lookups {
 /// Took this out of `print_result` as
 fn format_suggestion_string(suggestions: &[Option<String>]) -> Result<()> {
     let mut suggestion_str = String::new();
-    for el in suggestions {
-        match el {
-            Some(s) => {
-                suggestion_str += &some_kind_of_uppercase_first_letter(&s[..]);
-                suggestion_str += ", ";
-            }
-            None => {}
-        }
+    for s in suggestions.iter().flatten() {
+        suggestion_str += &some_kind_of_uppercase_first_letter(&s[..]);
+        suggestion_str += ", ";
     }
     // remove last comma
     if suggestion_str.len() > 2 {
         suggestion_str.drain(suggestion_str.len() - 2..);
-        return Err(Error::new(ErrorKind::GenericCli(format!(
+        Err(Error::new(ErrorKind::GenericCli(format!(
             "did you mean: {}?",
             suggestion_str
-        ))));
+        ))))
     } else {
-        return Err(Error::new(ErrorKind::GenericCli(format!(
-            "there are no results."
-        ))));
+        Err(Error::new(ErrorKind::GenericCli(
+            "there are no results.".to_string(),
+        )))
     }
 }
 
@@ -178,16 +178,8 @@ impl TaxonCollector {
                         match taxon_names_op {
                             Some(n) => {
                                 // get taxon_ids and taxon_ranks
-                                let taxon_ids = match taxon_ids_op {
-                                    Some(t) => t,
-                                    // empty vec
-                                    None => vec![],
-                                };
-                                let taxon_ranks = match taxon_ranks_op {
-                                    Some(t) => t,
-                                    // empty vec
-                                    None => vec![],
-                                };
+                                let taxon_ids = taxon_ids_op.unwrap_or_default();
+                                let taxon_ranks = taxon_ranks_op.unwrap_or_default();
                                 // zip these vectors together
                                 let zipped_taxon_vectors =
                                     taxon_ids.iter().zip(taxon_ranks.iter()).zip(n.iter());
@@ -208,18 +200,17 @@ impl TaxonCollector {
                                 }
                                 // remove trailing newline
                                 whole_res_string.pop();
-                                Ok(println!("{}", whole_res_string))
-                            }
-                            None => {
-                                return Err(Error::new(ErrorKind::GenericCli(format!(
-                                    "there were no taxon names."
-                                ))))
+                                println!("{}", whole_res_string);
+                                Ok(())
                             }
+                            None => Err(Error::new(ErrorKind::GenericCli(
+                                "there were no taxon names.".to_string(),
+                            ))),
                         }
                     }
                 }
             }
-            None => return Err(Error::new(ErrorKind::GenericCli(format!("no results.")))),
+            None => Err(Error::new(ErrorKind::GenericCli("no results.".to_string()))),
         }
     }
 }
@@ -270,11 +261,7 @@ impl AssemblyCollector {
                         match assembly_identifiers_op {
                             Some(n) => {
                                 // get taxon_ids and taxon_ranks
-                                let taxon_ids = match taxon_ids_op {
-                                    Some(t) => t,
-                                    // empty vec
-                                    None => vec![],
-                                };
+                                let taxon_ids = taxon_ids_op.unwrap_or_default();
                                 // zip these vectors together
                                 let zipped_taxon_vectors = taxon_ids.iter().zip(n.iter());
 
@@ -294,22 +281,19 @@ impl AssemblyCollector {
                                 }
                                 // remove trailing newline
                                 whole_res_string.pop();
-                                Ok(println!("{}", whole_res_string))
-                            }
-                            None => {
-                                return Err(Error::new(ErrorKind::GenericCli(format!(
-                                    "there were no taxon names."
-                                ))))
+                                println!("{}", whole_res_string);
+                                Ok(())
                             }
+                            None => Err(Error::new(ErrorKind::GenericCli(
+                                "there were no taxon names.".to_string(),
+                            ))),
                         }
                     }
                 }
             }
-            None => {
-                return Err(Error::new(ErrorKind::GenericCli(format!(
-                    "there are no results."
-                ))))
-            }
+            None => Err(Error::new(ErrorKind::GenericCli(
+                "there are no results.".to_string(),
+            ))),
         }
     }
 }
@@ -327,13 +311,14 @@ pub enum Collector {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::IndexType;
+    use crate::{IndexType, GOAT_URL};
 
     fn taxon_lookup(search: &str, size: u64) -> Lookup {
         Lookup {
             search: search.into(),
             size,
             index_type: IndexType::Taxon,
+            goat_url: GOAT_URL.clone(),
         }
     }
 
@@ -342,6 +327,7 @@ mod tests {
             search: search.into(),
             size,
             index_type: IndexType::Assembly,
+            goat_url: GOAT_URL.clone(),
         }
     }
 
@@ -384,6 +370,14 @@ mod tests {
         assert!(url.contains("lookup?"));
     }
 
+    #[test]
+    fn test_make_url_uses_configured_goat_url() {
+        let mut lookup = taxon_lookup("Mammalia", 10);
+        lookup.goat_url = "http://localhost:3000/api/v2/".into();
+        let url = lookup.make_url();
+        assert!(url.starts_with("http://localhost:3000/api/v2/lookup?"));
+    }
+
     // ── Lookups::make_urls ───────────────────────────────────────────────────
 
     #[test]
@@ -414,7 +408,7 @@ mod tests {
 
     #[test]
     fn test_make_urls_preserves_order() {
-        let taxa = vec!["Zeta", "Alpha", "Gamma"];
+        let taxa = ["Zeta", "Alpha", "Gamma"];
         let lookups = Lookups {
             entries: taxa
                 .iter()
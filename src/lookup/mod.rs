@@ -9,6 +9,7 @@ use futures::StreamExt;
 use serde_json::Value;
 
 /// The inner structs used in lookup.
+#[allow(clippy::module_inception)]
 pub mod lookup;
 use lookup::{AssemblyCollector, Collector, Lookups, TaxonCollector};
 
@@ -49,16 +50,13 @@ pub async fn lookup(
                     let v: Value = client.get_json(&path).await?;
                             // print a warning if number of hits > size specified.
                             let request_size_op = &v["status"]["hits"].as_u64();
-                            match request_size_op {
-                                Some(s) => {
-                                    if size < *s {
-                                        eprintln!(
-                                            "For seach query {}, size specified ({}) was less than the number of results returned, ({}).",
-                                            search_query, size, s
-                                        )
-                                    }
+                            if let Some(s) = request_size_op {
+                                if size < *s {
+                                    eprintln!(
+                                        "For seach query {}, size specified ({}) was less than the number of results returned, ({}).",
+                                        search_query, size, s
+                                    )
                                 }
-                                None => (),
                             }
 
                             // get all the suggestions
@@ -6,7 +6,10 @@ use goat_cli::{
     cli, count, lookup, progress,
     report::{self, report::ReportType},
     search,
-    utils::utils::{generate_unique_strings, UniqueIdAction},
+    utils::catalogue,
+    utils::utils::{
+        generate_report_ids, generate_unique_strings, goat_url, random_ids, UniqueIdAction,
+    },
     IndexType,
 };
 
@@ -23,6 +26,23 @@ async fn main() {
 async fn run() -> Result<()> {
     let matches = cli::build_cli().get_matches();
 
+    // fetch the current GoaT variables, so any added since this
+    // release can be used in `-v`/`-e`.
+    let goat_url = goat_url(&matches)?;
+    // Only commands checking variables wait for this.
+    if let Some((index, index_matches)) = matches.subcommand() {
+        let index_type = match index {
+            "taxon" => Some(IndexType::Taxon),
+            "assembly" => Some(IndexType::Assembly),
+            _ => None,
+        };
+        if let (Some(index_type), Some((_, command_matches))) =
+            (index_type, index_matches.subcommand())
+        {
+            catalogue::load_for(command_matches, &goat_url, index_type).await;
+        }
+    }
+
     // nested matching on subcommands
     match matches.subcommand() {
         // outer == taxon/assembly
@@ -70,6 +90,26 @@ async fn run() -> Result<()> {
                     taxon_sources_matches,
                     unique_ids,
                     ReportType::Sources,
+                    IndexType::Taxon,
+                )
+                .await?
+                {
+                    ReportAction::Continue => {}
+                    ReportAction::PrintedAndExit => return Ok(()),
+                };
+            }
+            Some(("files", taxon_files_matches)) => {
+                let unique_ids =
+                    match generate_unique_strings(taxon_files_matches, IndexType::Taxon)? {
+                        UniqueIdAction::Continue(ids) => ids,
+                        UniqueIdAction::PrintedAndExit => return Ok(()),
+                    };
+
+                match report::fetch::fetch_report(
+                    taxon_files_matches,
+                    unique_ids,
+                    ReportType::Files,
+                    IndexType::Taxon,
                 )
                 .await?
                 {
@@ -110,6 +150,7 @@ async fn run() -> Result<()> {
                     taxon_hist_matches,
                     unique_ids,
                     ReportType::Histogram,
+                    IndexType::Taxon,
                 )
                 .await?
                 {
@@ -127,6 +168,62 @@ async fn run() -> Result<()> {
                     scatter_matches,
                     unique_ids,
                     ReportType::Scatterplot,
+                    IndexType::Taxon,
+                )
+                .await?
+                {
+                    ReportAction::Continue => {}
+                    ReportAction::PrintedAndExit => return Ok(()),
+                };
+            }
+            Some(("map", map_matches)) => {
+                let unique_ids = match generate_unique_strings(map_matches, IndexType::Taxon)? {
+                    UniqueIdAction::Continue(ids) => ids,
+                    UniqueIdAction::PrintedAndExit => return Ok(()),
+                };
+
+                match report::fetch::fetch_report(
+                    map_matches,
+                    unique_ids,
+                    ReportType::Map,
+                    IndexType::Taxon,
+                )
+                .await?
+                {
+                    ReportAction::Continue => {}
+                    ReportAction::PrintedAndExit => return Ok(()),
+                };
+            }
+            Some(("per-rank", per_rank_matches)) => {
+                let unique_ids = match generate_unique_strings(per_rank_matches, IndexType::Taxon)?
+                {
+                    UniqueIdAction::Continue(ids) => ids,
+                    UniqueIdAction::PrintedAndExit => return Ok(()),
+                };
+
+                match report::fetch::fetch_report(
+                    per_rank_matches,
+                    unique_ids,
+                    ReportType::PerRank,
+                    IndexType::Taxon,
+                )
+                .await?
+                {
+                    ReportAction::Continue => {}
+                    ReportAction::PrintedAndExit => return Ok(()),
+                };
+            }
+            Some(("arc", arc_matches)) => {
+                let unique_ids = match generate_unique_strings(arc_matches, IndexType::Taxon)? {
+                    UniqueIdAction::Continue(ids) => ids,
+                    UniqueIdAction::PrintedAndExit => return Ok(()),
+                };
+
+                match report::fetch::fetch_report(
+                    arc_matches,
+                    unique_ids,
+                    ReportType::Arc,
+                    IndexType::Taxon,
                 )
                 .await?
                 {
@@ -144,17 +241,15 @@ async fn run() -> Result<()> {
                     .copied()
                     .unwrap_or(false);
 
-                let unique_ids =
-                    match generate_unique_strings(taxon_newick_matches, IndexType::Taxon)? {
-                        UniqueIdAction::Continue(ids) => ids,
-                        UniqueIdAction::PrintedAndExit => return Ok(()),
-                    };
+                // an ID for each batch of taxa
+                let unique_ids = generate_report_ids(taxon_newick_matches)?;
 
                 if print_url {
                     match report::fetch::fetch_report(
                         taxon_newick_matches,
                         unique_ids,
                         ReportType::Newick,
+                        IndexType::Taxon,
                     )
                     .await?
                     {
@@ -166,7 +261,8 @@ async fn run() -> Result<()> {
                         report::fetch::fetch_report(
                             taxon_newick_matches,
                             unique_ids.clone(),
-                            ReportType::Newick
+                            ReportType::Newick,
+                            IndexType::Taxon
                         ),
                         progress::progress_bar(
                             taxon_newick_matches,
@@ -185,6 +281,7 @@ async fn run() -> Result<()> {
                         taxon_newick_matches,
                         unique_ids,
                         ReportType::Newick,
+                        IndexType::Taxon,
                     )
                     .await?
                     {
@@ -254,6 +351,86 @@ async fn run() -> Result<()> {
                     lookup::LookupAction::PrintedAndExit => return Ok(()),
                 }
             }
+            Some(("hist", assembly_hist_matches)) => {
+                let unique_ids =
+                    match generate_unique_strings(assembly_hist_matches, IndexType::Assembly)? {
+                        UniqueIdAction::Continue(ids) => ids,
+                        UniqueIdAction::PrintedAndExit => return Ok(()),
+                    };
+
+                match report::fetch::fetch_report(
+                    assembly_hist_matches,
+                    unique_ids,
+                    ReportType::Histogram,
+                    IndexType::Assembly,
+                )
+                .await?
+                {
+                    ReportAction::Continue => {}
+                    ReportAction::PrintedAndExit => return Ok(()),
+                };
+            }
+            Some(("oxford", assembly_oxford_matches)) => {
+                // a single report of two assemblies
+                let unique_ids = random_ids(1);
+
+                match report::fetch::fetch_report(
+                    assembly_oxford_matches,
+                    unique_ids,
+                    ReportType::Oxford,
+                    IndexType::Assembly,
+                )
+                .await?
+                {
+                    ReportAction::Continue => {}
+                    ReportAction::PrintedAndExit => return Ok(()),
+                };
+            }
+            Some(("newick", assembly_newick_matches)) => {
+                let progress_bar = *assembly_newick_matches
+                    .get_one::<bool>("progress-bar")
+                    .expect("cli default false");
+                let print_url = *assembly_newick_matches
+                    .get_one::<bool>("url")
+                    .expect("cli default false");
+
+                // an ID for each batch of taxa
+                let unique_ids = generate_report_ids(assembly_newick_matches)?;
+
+                let report_action = match progress_bar && !print_url {
+                    true => {
+                        let (report_action, _) = try_join!(
+                            report::fetch::fetch_report(
+                                assembly_newick_matches,
+                                unique_ids.clone(),
+                                ReportType::Newick,
+                                IndexType::Assembly
+                            ),
+                            progress::progress_bar(
+                                assembly_newick_matches,
+                                "newick",
+                                unique_ids,
+                                IndexType::Assembly
+                            )
+                        )?;
+                        report_action
+                    }
+                    false => {
+                        report::fetch::fetch_report(
+                            assembly_newick_matches,
+                            unique_ids,
+                            ReportType::Newick,
+                            IndexType::Assembly,
+                        )
+                        .await?
+                    }
+                };
+
+                match report_action {
+                    ReportAction::Continue => {}
+                    ReportAction::PrintedAndExit => return Ok(()),
+                }
+            }
             _ => unreachable!(),
         },
         _ => unreachable!(),
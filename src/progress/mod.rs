@@ -9,12 +9,23 @@ use indicatif;
 use serde_json::Value;
 use std::time::Duration;
 use tokio::time::sleep;
+use url::Url;
 
 use crate::client::GoatClient;
 use crate::error::Result;
 use crate::utils::cli_matches::{self, CliAction};
+use crate::utils::utils::goat_url;
+use crate::UPPER_CLI_SIZE_LIMIT;
 use crate::{count, IndexType};
-use crate::{GOAT_URL, UPPER_CLI_SIZE_LIMIT};
+
+/// The `queryId` of a GoaT API URL.
+fn query_id(url: &str) -> Option<String> {
+    Url::parse(url)
+        .ok()?
+        .query_pairs()
+        .find(|(key, _)| key == "queryId")
+        .map(|(_, value)| value.into_owned())
+}
 
 // a function to create and display a progress bar
 // for large requests. Currently limited to single large requests.
@@ -55,12 +66,24 @@ pub async fn progress_bar(
         }
     }
 
-    // add the query ID's to a vec
-    let mut query_id_vec = Vec::new();
-    for i in unique_ids.iter().take(concurrent_requests) {
-        let query_id = format!("{}progress?queryId=goat_cli_{}", *GOAT_URL, i);
-        query_id_vec.push(query_id);
-    }
+    // add the query ID's to a vec. Searches take them from their
+    // URLs, as each branch of an expression using OR has its own.
+    let goat_url = goat_url(matches)?;
+    let query_ids = match api {
+        "newick" => unique_ids
+            .iter()
+            .take(concurrent_requests)
+            .map(|i| format!("goat_cli_{}", i))
+            .collect(),
+        _ => url_vector_api
+            .iter()
+            .filter_map(|url| query_id(url))
+            .collect::<Vec<String>>(),
+    };
+    let query_id_vec = query_ids
+        .iter()
+        .map(|query_id| format!("{}progress?queryId={}", goat_url, query_id))
+        .collect::<Vec<String>>();
 
     // we want to wrap this in a loop
     // and break when sum(progress_x) == sum(progress_total)
@@ -106,10 +129,7 @@ pub async fn progress_bar(
         let mut progress_x_total = 0;
         let mut progress_total_total = 0;
         for el in progress_total? {
-            let x_tot_tup = match el {
-                Some(t) => t,
-                None => (None, None),
-            };
+            let x_tot_tup = el.unwrap_or_default();
             progress_x_total += x_tot_tup.0.unwrap_or(0);
             progress_total_total += x_tot_tup.1.unwrap_or(0);
         }
@@ -134,3 +154,15 @@ pub async fn progress_bar(
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_query_id_of_or_branch() {
+        let url = "https://goat.genomehubs.org/api/v2/search?query=tax_tree%28Aves%29&queryId=goat_cli_abc_1&size=10";
+        assert_eq!(query_id(url).as_deref(), Some("goat_cli_abc_1"));
+        assert_eq!(query_id("https://goat.genomehubs.org/api/v2/search"), None);
+    }
+}
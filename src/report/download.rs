@@ -0,0 +1,184 @@
+use crate::client::GoatClient;
+use crate::error::{Error, ErrorKind, Result};
+use crate::report::models::FileEntry;
+use futures::StreamExt;
+use md5::Md5;
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+/// The number of files to download at once.
+const CONCURRENT_DOWNLOADS: usize = 4;
+
+/// The name to save a file as in the download directory. Only
+/// the final component of the name is used, so files cannot be
+/// written outside of the directory.
+fn file_name(entry: &FileEntry) -> Option<String> {
+    Path::new(&entry.name)
+        .file_name()
+        .or_else(|| Path::new(entry.url.rsplit('/').next()?).file_name())
+        .map(|n| n.to_string_lossy().into_owned())
+}
+
+/// The names to save `entries` as. Entries with the same URL are
+/// downloaded once, and names shared by different URLs (e.g. an
+/// image name used by two taxa) are prefixed with a hash of the
+/// URL, so no two downloads write to the same path.
+fn unique_file_names(entries: &[FileEntry]) -> Result<Vec<(&FileEntry, String)>> {
+    let mut seen_urls = HashSet::new();
+    let named = entries
+        .iter()
+        .filter(|entry| seen_urls.insert(entry.url.as_str()))
+        .map(|entry| {
+            let name = file_name(entry).ok_or_else(|| {
+                Error::new(ErrorKind::Report(format!(
+                    "could not name the file at {}.",
+                    entry.url
+                )))
+            })?;
+            Ok((entry, name))
+        })
+        .collect::<Result<Vec<(&FileEntry, String)>>>()?;
+
+    let mut counts = HashMap::new();
+    for (_, name) in &named {
+        *counts.entry(name.clone()).or_insert(0) += 1;
+    }
+    Ok(named
+        .into_iter()
+        .map(|(entry, name)| match counts[&name] {
+            1 => (entry, name),
+            _ => {
+                let hash = format!("{:x}", Sha256::digest(entry.url.as_bytes()));
+                (entry, format!("{}_{}", &hash[..8], name))
+            }
+        })
+        .collect())
+}
+
+/// Check a downloaded file against the checksums in `entry`, if
+/// there are any.
+fn verify(entry: &FileEntry, path: &Path) -> Result<()> {
+    if entry.md5.is_none() && entry.sha256.is_none() {
+        return Ok(());
+    }
+    let bytes = std::fs::read(path)?;
+    let checks = [
+        (
+            entry.md5.as_deref(),
+            "MD5",
+            format!("{:x}", Md5::digest(&bytes)),
+        ),
+        (
+            entry.sha256.as_deref(),
+            "SHA-256",
+            format!("{:x}", Sha256::digest(&bytes)),
+        ),
+    ];
+    for (expected, algorithm, actual) in checks {
+        if let Some(expected) = expected {
+            if !expected.eq_ignore_ascii_case(&actual) {
+                // remove it, so it is fetched again next time
+                std::fs::remove_file(path)?;
+                return Err(Error::new(ErrorKind::Report(format!(
+                    "{} checksum of {} did not match: expected {}, found {}.",
+                    algorithm,
+                    path.display(),
+                    expected,
+                    actual
+                ))));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Download the files in `entries` to `dir`, a few at a time,
+/// checking any checksums. Interrupted downloads are resumed
+/// (see [`GoatClient::download`]).
+///
+/// Returns the paths of the downloaded files.
+pub async fn download_files(
+    client: &GoatClient,
+    entries: &[FileEntry],
+    dir: &Path,
+) -> Result<Vec<PathBuf>> {
+    std::fs::create_dir_all(dir)?;
+
+    let named = unique_file_names(entries)?;
+    let downloads = futures::stream::iter(named.into_iter().map(|(entry, name)| async move {
+        let path = dir.join(name);
+        client.download(&entry.url, &path).await?;
+        verify(entry, &path)?;
+        eprintln!("Downloaded {}", path.display());
+        Ok(path)
+    }))
+    .buffer_unordered(CONCURRENT_DOWNLOADS)
+    .collect::<Vec<Result<PathBuf>>>()
+    .await;
+
+    downloads.into_iter().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(name: &str, url: &str) -> FileEntry {
+        FileEntry {
+            name: name.into(),
+            file_type: "image".into(),
+            size: None,
+            url: url.into(),
+            md5: None,
+            sha256: None,
+        }
+    }
+
+    #[test]
+    fn test_file_name_stays_in_dir() {
+        let e = entry("../../etc/passwd", "https://example.org/a.jpg");
+        assert_eq!(file_name(&e).as_deref(), Some("passwd"));
+        let e = entry("", "https://example.org/images/a.jpg");
+        assert_eq!(file_name(&e).as_deref(), Some("a.jpg"));
+    }
+
+    #[test]
+    fn test_unique_file_names() {
+        let entries = vec![
+            entry("hs.jpg", "https://example.org/9606/hs.jpg"),
+            entry("hs.jpg", "https://example.org/9605/hs.jpg"),
+            // the same file listed twice is downloaded once
+            entry("hs.jpg", "https://example.org/9606/hs.jpg"),
+            entry("Pan.jpg", "https://example.org/9598/Pan.jpg"),
+        ];
+        let names: Vec<String> = unique_file_names(&entries)
+            .unwrap()
+            .into_iter()
+            .map(|(_, name)| name)
+            .collect();
+        assert_eq!(names.len(), 3);
+        assert_ne!(names[0], names[1]);
+        assert!(names[0].ends_with("_hs.jpg"));
+        assert!(names[1].ends_with("_hs.jpg"));
+        assert_eq!(names[2], "Pan.jpg");
+    }
+
+    #[test]
+    fn test_verify_checksums() {
+        let dir = std::env::temp_dir().join("goat_cli_test_verify_checksums");
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("hello.txt");
+        std::fs::write(&path, "hello").unwrap();
+
+        let mut e = entry("hello.txt", "https://example.org/hello.txt");
+        e.md5 = Some("5D41402ABC4B2A76B9719D911017C592".into());
+        e.sha256 = Some("2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824".into());
+        assert!(verify(&e, &path).is_ok());
+
+        e.md5 = Some("00000000000000000000000000000000".into());
+        assert!(verify(&e, &path).is_err());
+        // the bad file is removed
+        assert!(!path.exists());
+    }
+}
@@ -1,26 +1,75 @@
 use crate::client::GoatClient;
 use crate::error::{Error, ErrorKind, Result};
+use crate::report::models::{
+    ArcCount, CountryCount, FileEntry, HistogramBin, OxfordPair, RankCount, ScatterBin,
+    SourceCount, Table, TreeNode,
+};
 use crate::report::report::{Report, ReportType};
+use crate::report::{countries, download, models, newick, plot, svg};
+use crate::utils::output::{self, OutputFormat};
+use crate::utils::utils;
+use crate::IndexType;
 use futures::StreamExt;
 use std::io::Write;
+use std::path::PathBuf;
 
 pub enum ReportAction {
     Continue,
     PrintedAndExit,
 }
 
+/// A report ready to write out.
+enum Rendered {
+    /// Text written as it is, e.g. a tree or a plot.
+    Text(String),
+    /// A table, written in the `--output-format`.
+    Table(models::Table),
+}
+
 /// CLI entry point to get the Newick file from the GoaT API.
 pub async fn fetch_report(
     matches: &clap::ArgMatches,
     unique_ids: Vec<String>,
     report_type: ReportType,
+    index_type: IndexType,
 ) -> Result<ReportAction> {
-    let report = Report::new(matches, report_type)?;
-    let url = report.make_url(unique_ids)?;
+    let report = Report::new(matches, report_type, index_type)?;
+    let urls = report.make_urls(unique_ids.clone())?;
+    // histograms can be drawn in the terminal instead
+    let plot = matches
+        .try_get_one::<bool>("plot")
+        .ok()
+        .flatten()
+        .copied()
+        .unwrap_or(false);
+    // histograms and scatters can also be drawn to an SVG file
+    let svg_path = matches.try_get_one::<PathBuf>("svg").ok().flatten();
+    // newick trees can be pruned, collapsed and relabelled locally
+    let prune_to = matches.try_get_one::<PathBuf>("prune-to").ok().flatten();
+    let collapse_monotypic = matches
+        .try_get_one::<bool>("collapse-monotypic")
+        .ok()
+        .flatten()
+        .copied()
+        .unwrap_or(false);
+    let label_taxid = matches
+        .try_get_one::<String>("label")
+        .ok()
+        .flatten()
+        .is_some_and(|label| label == "taxid");
+    // the taxon IDs and variable values of the tips need a search
+    let search_tips = !report.variables.is_empty() || label_taxid || prune_to.is_some();
 
     let print_url = *matches.get_one::<bool>("url").expect("cli default false");
     if print_url {
-        println!("GoaT lookup API URL:\t{}", url);
+        for url in &urls {
+            println!("GoaT lookup API URL:\t{}", url);
+        }
+        if report_type == ReportType::Newick && search_tips {
+            for url in report.search_urls(&unique_ids) {
+                println!("GoaT search API URL:\t{}", url);
+            }
+        }
         return Ok(ReportAction::PrintedAndExit);
     }
 
@@ -28,44 +77,223 @@ pub async fn fetch_report(
     // more schemas could be defined here.
     let header_value = match report_type {
         ReportType::Newick => "text/x-nh",
-        _ => "text/tab-separated-values",
+        // parsed into typed models below
+        _ => "application/json",
     };
 
-    // for now, you can only submit a single request at once.
-    let concurrent_requests = 1;
-
-    // but for future work, might be useful to have concurrent requests
-    // for now this is a bit of extra work for a single request.
-    // but whatever!
-    let url_vector_api = vec![url];
+    // large newick trees are fetched in batches, a few at a time.
+    let concurrent_requests = urls.len().min(4);
 
     let client = GoatClient::new();
-    let fetches = futures::stream::iter(url_vector_api.into_iter().map(|path| {
+    let fetches = futures::stream::iter(urls.into_iter().map(|path| {
         let client = client.clone();
         async move { client.get_text(&path, header_value).await }
     }))
     .buffered(concurrent_requests)
     .collect::<Vec<_>>();
 
-    let mut awaited_fetches = fetches.await;
+    let responses = fetches.await.into_iter().collect::<Result<Vec<String>>>()?;
 
-    let report = awaited_fetches.remove(0);
+    // reports are written as a table in `--output-format`, except
+    // for plots and trees, which are written as they are.
+    let output_format = matches
+        .try_get_one::<String>("output-format")
+        .ok()
+        .flatten()
+        .is_some();
+    let format = OutputFormat::from_matches(matches);
 
-    match report {
-        Ok(ref s) => {
-            // check the length of the string
-            // if it's zero, then we have an error
-            if s.is_empty() || s == ";" {
-                return Err(Error::new(ErrorKind::Report(
-                    "no data found. If it was a `taxon newick` call, try increasing the threshold."
-                        .to_string(),
-                )));
+    let rendered = match report_type {
+        ReportType::Newick => {
+            // merge the trees of each batch, skipping any empty ones.
+            let batches = responses
+                .iter()
+                .filter(|s| !s.trim().is_empty() && s.trim() != ";")
+                .map(|s| newick::parse(s))
+                .collect::<Result<Vec<newick::Node>>>()?;
+            // join the batches at their common ancestor, from a
+            // tree of the root of each batch.
+            let roots = batches
+                .iter()
+                .map(|b| b.name.clone())
+                .collect::<Vec<String>>();
+            let ancestors = match batches.len() > 1 && roots.iter().all(|r| !r.is_empty()) {
+                true => {
+                    let unique_id = &utils::random_ids(1)[0];
+                    let url = report.ancestors_url(&roots, unique_id);
+                    let newick = client.get_text(&url, header_value).await?;
+                    match newick.trim() {
+                        "" | ";" => None,
+                        newick => Some(newick::parse(newick)?),
+                    }
+                }
+                false => None,
+            };
+            let merged = match ancestors {
+                Some(ancestors) => Some(newick::merge_at_ancestor(ancestors, batches)),
+                None => batches.into_iter().reduce(newick::merge),
+            };
+            let mut tree = merged
+                .filter(|tree| !tree.tips().is_empty())
+                .ok_or_else(|| {
+                    Error::new(ErrorKind::Report(
+                        "no data found. If it was a `taxon newick` call, try increasing the threshold."
+                            .to_string(),
+                    ))
+                })?;
+            let records = match search_tips {
+                true => {
+                    let mut records = vec![];
+                    for url in report.search_urls(&unique_ids) {
+                        let search = client.get_json(&url).await?;
+                        records.extend(output::search_records(&search, &report.search.join(","))?);
+                    }
+                    records
+                }
+                false => vec![],
+            };
+            let taxon_ids = newick::taxon_ids(&records);
+            newick::annotate(&mut tree, &records, &report.variables);
+            if let Some(path) = prune_to {
+                let keep = utils::lines_from_file(path)?;
+                if newick::prune(&mut tree, &keep, &taxon_ids) == 0 {
+                    return Err(Error::new(ErrorKind::Report(format!(
+                        "none of the tips in {} were found in the tree.",
+                        path.display()
+                    ))));
+                }
+            }
+            if collapse_monotypic {
+                tree.collapse_monotypic();
             }
+            if label_taxid {
+                newick::relabel(&mut tree, &taxon_ids);
+            }
+            let annotation_format = matches
+                .get_one::<String>("annotation-format")
+                .expect("cli default nhx");
+            match (output_format, annotation_format.as_str()) {
+                (true, _) => Rendered::Table(models::table(&TreeNode::from_tree(&tree))),
+                (false, "phyloxml") => Rendered::Text(tree.to_phyloxml()),
+                (false, "itol") => {
+                    // the datasets carry the values, so the tree is left plain
+                    let prefix = matches
+                        .get_one::<String>("itol-prefix")
+                        .expect("cli default goat");
+                    for variable in &report.variables {
+                        let path = format!("{}_{}.txt", prefix, variable);
+                        std::fs::write(&path, newick::itol_dataset(&tree, variable))?;
+                        eprintln!("Wrote {}", path);
+                    }
+                    Rendered::Text(tree.without_annotations().to_newick())
+                }
+                _ => Rendered::Text(tree.to_newick()),
+            }
+        }
+        _ => {
+            let mut rendered = vec![];
+            for response in &responses {
+                let json = models::parse_json(response, report_type)?;
+                rendered.push(match report_type {
+                    ReportType::Scatterplot => {
+                        if let Some(path) = svg_path {
+                            let titles = (
+                                report.x.as_deref().unwrap_or("x"),
+                                report.y.as_deref().unwrap_or("y"),
+                            );
+                            let drawing = svg::scatter(
+                                &json,
+                                report.x_opts.as_ref(),
+                                report.y_opts.as_ref(),
+                                titles,
+                            )?;
+                            std::fs::write(path, drawing)?;
+                        }
+                        Rendered::Table(models::table(&ScatterBin::from_response(&json)?))
+                    }
+                    ReportType::Sources => {
+                        Rendered::Table(models::table(&SourceCount::from_response(&json)?))
+                    }
+                    ReportType::Arc => {
+                        Rendered::Table(models::table(&[ArcCount::from_response(&json)?]))
+                    }
+                    ReportType::PerRank => {
+                        Rendered::Table(models::table(&RankCount::from_response(&json)?))
+                    }
+                    ReportType::Oxford => Rendered::Table(OxfordPair::table(
+                        &OxfordPair::from_response(&json)?,
+                        &report.search,
+                    )),
+                    ReportType::Files => {
+                        let entries = FileEntry::from_response(&json)?;
+                        if let Some(dir) = matches
+                            .try_get_one::<PathBuf>("download-dir")
+                            .ok()
+                            .flatten()
+                        {
+                            download::download_files(&client, &entries, dir).await?;
+                        }
+                        Rendered::Table(models::table(&entries))
+                    }
+                    ReportType::Map => {
+                        let counts = CountryCount::from_response(&json)?;
+                        if let Some(path) = matches.try_get_one::<PathBuf>("geojson").ok().flatten()
+                        {
+                            std::fs::write(path, countries::geojson(&counts).to_string())?;
+                        }
+                        Rendered::Table(models::table(&counts))
+                    }
+                    _ => {
+                        let report_name = report_type.to_string();
+                        if let Some(path) = svg_path {
+                            let drawing = svg::histogram(
+                                &json,
+                                &report_name,
+                                report.x_opts.as_ref(),
+                                report.x.as_deref().unwrap_or("x"),
+                            )?;
+                            std::fs::write(path, drawing)?;
+                        }
+                        match plot {
+                            true => Rendered::Text(plot::histogram(
+                                &json,
+                                &report_name,
+                                report.x_opts.as_ref(),
+                            )?),
+                            false => Rendered::Table(models::table(&HistogramBin::from_response(
+                                &json,
+                                &report_name,
+                            )?)),
+                        }
+                    }
+                });
+            }
+            match report.per_taxon {
+                // plots conflict with --per-taxon, so these are all tables
+                true => Rendered::Table(Table::merge(
+                    report
+                        .search
+                        .iter()
+                        .cloned()
+                        .zip(rendered)
+                        .filter_map(|(taxon, rendered)| match rendered {
+                            Rendered::Table(table) => Some((taxon, table)),
+                            Rendered::Text(_) => None,
+                        })
+                        .collect(),
+                )),
+                false => rendered.remove(0),
+            }
+        }
+    };
 
-            let mut stdout = std::io::stdout();
-            writeln!(stdout, "{}", s)?;
+    let mut stdout = std::io::stdout();
+    match rendered {
+        Rendered::Text(text) => writeln!(stdout, "{}", text)?,
+        Rendered::Table(table) => {
+            format.check_stdout()?;
+            table.write(format, stdout)?;
         }
-        Err(e) => return Err(e),
     }
 
     Ok(ReportAction::Continue)
@@ -1,4 +1,17 @@
+/// Country centroids, for GeoJSON output of map reports.
+pub mod countries;
+/// Download the files listed in a files report.
+pub mod download;
 /// Fetch the corresponding report from the GoaT API.
 pub mod fetch;
+/// Typed models of report responses, and tables to write them as.
+pub mod models;
+/// Parse, annotate and write Newick trees.
+pub mod newick;
+/// Render histogram reports in the terminal.
+pub mod plot;
 /// Module providing parsing functionality for GoaT reports.
+#[allow(clippy::module_inception)]
 pub mod report;
+/// Draw histogram and scatter reports as SVG.
+pub mod svg;
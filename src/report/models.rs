@@ -0,0 +1,991 @@
+use crate::error::{Error, ErrorKind, Result};
+use crate::report::newick::Node;
+use crate::report::plot::histogram_bins;
+use crate::report::report::ReportType;
+use crate::utils::columnar::{Column, ColumnType, ParquetWriter};
+use crate::utils::output::{csv_field, JsonWriter, OutputFormat};
+use serde_json::{json, Value};
+use std::io::Write;
+
+/// Parse the JSON of a report response, with an error naming
+/// the report if it is malformed.
+pub fn parse_json(text: &str, report_type: ReportType) -> Result<Value> {
+    if text.trim().is_empty() {
+        return Err(Error::new(ErrorKind::Report(format!(
+            "the {} report response was empty.",
+            report_type
+        ))));
+    }
+    serde_json::from_str(text).map_err(|e| {
+        Error::new(ErrorKind::Report(format!(
+            "the {} report response was not valid JSON: {}.",
+            report_type, e
+        )))
+    })
+}
+
+/// Get the body of a report (e.g. `report.scatter`) from a
+/// JSON response, passing on any error the API reports.
+pub(crate) fn report_body<'a>(response: &'a Value, name: &str) -> Result<&'a Value> {
+    let body = &response["report"][name];
+    if body.is_null() {
+        return Err(Error::new(ErrorKind::Report(format!(
+            "no {} report in the API response.",
+            name
+        ))));
+    }
+    if body["status"]["success"] == false {
+        let message = body["status"]["error"]
+            .as_str()
+            .unwrap_or("the API could not build the report");
+        return Err(Error::new(ErrorKind::Report(message.to_string())));
+    }
+    Ok(body)
+}
+
+/// Format a JSON scalar for a TSV cell.
+pub(crate) fn cell(value: &Value) -> String {
+    match value {
+        Value::Null => "".into(),
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// The lower and upper bounds of bucket `i`. The last bucket
+/// has no upper bound.
+fn bounds(buckets: &[Value], i: usize) -> (Value, Value) {
+    (
+        buckets.get(i).cloned().unwrap_or_default(),
+        buckets.get(i + 1).cloned().unwrap_or_default(),
+    )
+}
+
+/// The error for a report with nothing in it.
+fn no_data(name: &str) -> Error {
+    Error::new(ErrorKind::Report(format!(
+        "no data found for the {} report.",
+        name
+    )))
+}
+
+/// A row of a report, with typed columns.
+pub trait Row {
+    /// The columns of a table of these rows.
+    fn columns() -> Vec<Column>;
+    /// This row as a JSON record, keyed by column name.
+    fn record(&self) -> Value;
+}
+
+/// A report as a table of typed columns, which can be written
+/// in any [`OutputFormat`].
+#[derive(Debug, Clone)]
+pub struct Table {
+    /// The columns, in order.
+    pub columns: Vec<Column>,
+    /// A JSON record per row.
+    pub records: Vec<Value>,
+}
+
+/// Make a table of `rows`. Reports without categories have no
+/// `category` column.
+pub fn table<R: Row>(rows: &[R]) -> Table {
+    let records: Vec<Value> = rows.iter().map(|r| r.record()).collect();
+    let mut columns = R::columns();
+    columns.retain(|c| c.name != "category" || records.iter().any(|r| !r["category"].is_null()));
+    Table { columns, records }
+}
+
+impl Table {
+    /// Rename a column, keeping where its values come from.
+    pub fn rename(mut self, from: &str, to: &str) -> Self {
+        for column in self.columns.iter_mut().filter(|c| c.name == from) {
+            column.name = to.to_string();
+        }
+        self
+    }
+
+    /// Merge the tables of a report made for each taxon into
+    /// one, with a `taxon` column first. Columns missing from
+    /// some of the tables are empty in their rows.
+    pub fn merge(tables: Vec<(String, Table)>) -> Self {
+        let mut columns = vec![Column::record("taxon", ColumnType::Text)];
+        let mut records = vec![];
+        for (taxon, table) in tables {
+            for column in table.columns {
+                if !columns.contains(&column) {
+                    columns.push(column);
+                }
+            }
+            records.extend(table.records.into_iter().map(|mut record| {
+                record["taxon"] = Value::String(taxon.clone());
+                record
+            }));
+        }
+        Table { columns, records }
+    }
+
+    /// The table as delimited text with a header, e.g. TSV.
+    /// CSV fields are quoted if needed.
+    pub fn to_delimited(&self, separator: char) -> String {
+        let field = |text: String| match separator {
+            ',' => csv_field(&text),
+            _ => text,
+        };
+        let mut lines = vec![self
+            .columns
+            .iter()
+            .map(|c| field(c.name.clone()))
+            .collect::<Vec<String>>()
+            .join(&separator.to_string())];
+        for record in &self.records {
+            lines.push(
+                self.columns
+                    .iter()
+                    .map(|c| field(cell(c.value(record))))
+                    .collect::<Vec<String>>()
+                    .join(&separator.to_string()),
+            );
+        }
+        lines.join("\n")
+    }
+
+    /// Write the table in `format`.
+    pub fn write<W: Write + Send>(&self, format: OutputFormat, mut writer: W) -> Result<()> {
+        match format {
+            OutputFormat::Tsv => writeln!(writer, "{}", self.to_delimited('\t'))?,
+            OutputFormat::Csv => writeln!(writer, "{}", self.to_delimited(','))?,
+            OutputFormat::Json | OutputFormat::Jsonl => {
+                // the records keyed by column name
+                let records: Vec<Value> = self
+                    .records
+                    .iter()
+                    .map(|record| {
+                        Value::Object(
+                            self.columns
+                                .iter()
+                                .map(|c| (c.name.clone(), c.value(record).clone()))
+                                .collect(),
+                        )
+                    })
+                    .collect();
+                let mut json_writer = JsonWriter::new(writer, format);
+                json_writer.write_records(&records)?;
+                json_writer.finish()?;
+            }
+            OutputFormat::Parquet => {
+                let mut parquet_writer = ParquetWriter::new(writer, self.columns.clone())?;
+                parquet_writer.write_records(&self.records)?;
+                parquet_writer.finish()?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A bin of a histogram report. Bounds may be numbers or
+/// dates, so are kept as they are in the response.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HistogramBin {
+    /// The lower bound.
+    pub min: Value,
+    /// The upper bound, which is null for the last bin.
+    pub max: Value,
+    /// The category, if the report was split by one.
+    pub category: Option<String>,
+    /// The number of taxa or assemblies in the bin.
+    pub count: u64,
+}
+
+impl HistogramBin {
+    /// Get the bins from a histogram report, with a bin per
+    /// category in each if there are categories.
+    pub fn from_response(response: &Value, report_name: &str) -> Result<Vec<Self>> {
+        let bins = histogram_bins(response, report_name)?;
+        let mut rows = vec![];
+        for i in 0..bins.len() {
+            let (min, max) = bounds(&bins.buckets, i);
+            for (c, count) in bins.bin(i).into_iter().enumerate() {
+                rows.push(HistogramBin {
+                    min: min.clone(),
+                    max: max.clone(),
+                    category: bins.labels.get(c).cloned(),
+                    count,
+                });
+            }
+        }
+        match rows.is_empty() {
+            true => Err(no_data("histogram")),
+            false => Ok(rows),
+        }
+    }
+}
+
+impl Row for HistogramBin {
+    fn columns() -> Vec<Column> {
+        vec![
+            Column::record("min", ColumnType::Text),
+            Column::record("max", ColumnType::Text),
+            Column::record("category", ColumnType::Text),
+            Column::record("count", ColumnType::Long),
+        ]
+    }
+
+    fn record(&self) -> Value {
+        json!({"min": self.min, "max": self.max, "category": self.category, "count": self.count})
+    }
+}
+
+/// A cell of the grid of a scatter report.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScatterBin {
+    /// The lower bound of the x bin.
+    pub x_min: Value,
+    /// The upper bound of the x bin.
+    pub x_max: Value,
+    /// The lower bound of the y bin.
+    pub y_min: Value,
+    /// The upper bound of the y bin.
+    pub y_max: Value,
+    /// The category, if the report was split by one.
+    pub category: Option<String>,
+    /// The number of taxa in the cell.
+    pub count: u64,
+}
+
+impl ScatterBin {
+    /// Get the cells from a scatter report, with a row per
+    /// category in each if there are categories.
+    pub fn from_response(response: &Value) -> Result<Vec<Self>> {
+        let scatter = report_body(response, "scatter")?;
+        let histograms = &scatter["histograms"];
+        let (x_buckets, y_buckets) = match (
+            histograms["buckets"].as_array(),
+            histograms["yBuckets"].as_array(),
+        ) {
+            (Some(x), Some(y)) => (x, y),
+            _ => return Err(no_data("scatter")),
+        };
+
+        let mut rows = vec![];
+        // each table is counts indexed by [x bin][y bin]
+        let mut add = |category: Option<String>, table: &Value| {
+            for (i, x_counts) in table.as_array().into_iter().flatten().enumerate() {
+                let (x_min, x_max) = bounds(x_buckets, i);
+                for (j, count) in x_counts.as_array().into_iter().flatten().enumerate() {
+                    let (y_min, y_max) = bounds(y_buckets, j);
+                    rows.push(ScatterBin {
+                        x_min: x_min.clone(),
+                        x_max: x_max.clone(),
+                        y_min,
+                        y_max,
+                        category: category.clone(),
+                        count: count.as_u64().unwrap_or(0),
+                    });
+                }
+            }
+        };
+
+        match scatter["cats"].as_array().filter(|c| !c.is_empty()) {
+            Some(cats) => {
+                for cat in cats {
+                    let key = cell(&cat["key"]);
+                    let label = cat["label"].as_str().map(String::from);
+                    add(
+                        Some(label.unwrap_or_else(|| key.clone())),
+                        &histograms["byCat"][&key],
+                    );
+                }
+            }
+            None => add(None, &histograms["allValues"]),
+        }
+
+        match rows.is_empty() {
+            true => Err(no_data("scatter")),
+            false => Ok(rows),
+        }
+    }
+}
+
+impl Row for ScatterBin {
+    fn columns() -> Vec<Column> {
+        vec![
+            Column::record("x_min", ColumnType::Text),
+            Column::record("x_max", ColumnType::Text),
+            Column::record("y_min", ColumnType::Text),
+            Column::record("y_max", ColumnType::Text),
+            Column::record("category", ColumnType::Text),
+            Column::record("count", ColumnType::Long),
+        ]
+    }
+
+    fn record(&self) -> Value {
+        json!({
+            "x_min": self.x_min,
+            "x_max": self.x_max,
+            "y_min": self.y_min,
+            "y_max": self.y_max,
+            "category": self.category,
+            "count": self.count
+        })
+    }
+}
+
+/// A source of data, from a sources report.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SourceCount {
+    /// The name of the source.
+    pub source: String,
+    /// The number of values it provides.
+    pub count: u64,
+    /// A link to the source, if there is one.
+    pub link: Option<String>,
+}
+
+impl SourceCount {
+    /// Get the sources from a sources report. The most used
+    /// sources come first.
+    pub fn from_response(response: &Value) -> Result<Vec<Self>> {
+        let sources = report_body(response, "sources")?;
+        let sources = sources.as_object().ok_or_else(|| no_data("sources"))?;
+
+        let mut rows: Vec<SourceCount> = sources
+            .iter()
+            .filter(|(name, _)| *name != "status")
+            .map(|(name, source)| SourceCount {
+                source: name.clone(),
+                count: source["count"].as_u64().unwrap_or(0),
+                link: source["url"].as_str().map(String::from),
+            })
+            .collect();
+        rows.sort_by(|a, b| b.count.cmp(&a.count).then(a.source.cmp(&b.source)));
+
+        match rows.is_empty() {
+            true => Err(no_data("sources")),
+            false => Ok(rows),
+        }
+    }
+}
+
+impl Row for SourceCount {
+    fn columns() -> Vec<Column> {
+        vec![
+            Column::record("source", ColumnType::Text),
+            Column::record("count", ColumnType::Long),
+            Column::record("link", ColumnType::Text),
+        ]
+    }
+
+    fn record(&self) -> Value {
+        json!({"source": self.source, "count": self.count, "link": self.link})
+    }
+}
+
+/// The counts of an arc report.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArcCount {
+    /// The rank the taxa are counted at.
+    pub rank: Value,
+    /// The number of taxa matching x.
+    pub x: u64,
+    /// The number of taxa matching y.
+    pub y: u64,
+    /// The fraction of y that match x.
+    pub fraction: Value,
+}
+
+impl ArcCount {
+    /// Get the counts from an arc report.
+    pub fn from_response(response: &Value) -> Result<Self> {
+        let arc = report_body(response, "arc")?;
+        let (x, y) = match (arc["x"].as_u64(), arc["y"].as_u64()) {
+            (Some(x), Some(y)) => (x, y),
+            _ => return Err(no_data("arc")),
+        };
+        let fraction = match (arc["arc"].is_number(), y) {
+            (true, _) => arc["arc"].clone(),
+            (false, 0) => json!(0.0),
+            (false, y) => json!(x as f64 / y as f64),
+        };
+        Ok(ArcCount {
+            rank: arc["rank"].clone(),
+            x,
+            y,
+            fraction,
+        })
+    }
+}
+
+impl Row for ArcCount {
+    fn columns() -> Vec<Column> {
+        vec![
+            Column::record("rank", ColumnType::Text),
+            Column::record("x", ColumnType::Long),
+            Column::record("y", ColumnType::Long),
+            Column::record("fraction", ColumnType::Float),
+        ]
+    }
+
+    fn record(&self) -> Value {
+        json!({"rank": self.rank, "x": self.x, "y": self.y, "fraction": self.fraction})
+    }
+}
+
+/// The number of taxa at a rank, from an xPerRank report.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RankCount {
+    /// The rank.
+    pub rank: String,
+    /// The number of taxa matching the query.
+    pub count: u64,
+}
+
+impl RankCount {
+    /// Get the counts at each rank from an xPerRank report.
+    pub fn from_response(response: &Value) -> Result<Vec<Self>> {
+        let per_rank = report_body(response, "xPerRank")?;
+        let ranks = per_rank.as_array().ok_or_else(|| no_data("xPerRank"))?;
+        let rows: Vec<RankCount> = ranks
+            .iter()
+            .map(|rank| RankCount {
+                rank: cell(&rank["rank"]),
+                count: rank["x"].as_u64().unwrap_or(0),
+            })
+            .collect();
+        match rows.is_empty() {
+            true => Err(no_data("xPerRank")),
+            false => Ok(rows),
+        }
+    }
+}
+
+impl Row for RankCount {
+    fn columns() -> Vec<Column> {
+        vec![
+            Column::record("rank", ColumnType::Text),
+            Column::record("count", ColumnType::Long),
+        ]
+    }
+
+    fn record(&self) -> Value {
+        json!({"rank": self.rank, "count": self.count})
+    }
+}
+
+/// A feature shared by the two assemblies of an Oxford report.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OxfordPair {
+    /// The category, the sequence of the first assembly.
+    pub category: String,
+    /// The feature, e.g. a BUSCO gene.
+    pub feature_id: String,
+    /// The position in the first assembly.
+    pub x: Value,
+    /// The position in the second assembly.
+    pub y: Value,
+}
+
+impl OxfordPair {
+    /// Get the pairs from an Oxford report. The points are in
+    /// `rawData`, keyed by category, and are sorted by category
+    /// then position.
+    pub fn from_response(response: &Value) -> Result<Vec<Self>> {
+        let oxford = report_body(response, "oxford")?;
+        let raw_data = oxford["rawData"]
+            .as_object()
+            .ok_or_else(|| no_data("oxford"))?;
+
+        let mut rows = vec![];
+        for (category, points) in raw_data {
+            for point in points.as_array().into_iter().flatten() {
+                if !point["x"].is_number() || !point["y"].is_number() {
+                    continue;
+                }
+                let feature_id = match &point["featureId"] {
+                    Value::Null => cell(&point["feature_id"]),
+                    id => cell(id),
+                };
+                rows.push(OxfordPair {
+                    category: category.clone(),
+                    feature_id,
+                    x: point["x"].clone(),
+                    y: point["y"].clone(),
+                });
+            }
+        }
+        let position = |v: &Value| v.as_f64().unwrap_or(0.0);
+        rows.sort_by(|a, b| {
+            a.category
+                .cmp(&b.category)
+                .then(position(&a.x).total_cmp(&position(&b.x)))
+        });
+
+        match rows.is_empty() {
+            true => Err(no_data("oxford")),
+            false => Ok(rows),
+        }
+    }
+
+    /// A table of `pairs`, with the positions named after the
+    /// two `assemblies`.
+    pub fn table(pairs: &[Self], assemblies: &[String]) -> Table {
+        let mut table = table(pairs);
+        for (column, assembly) in ["x", "y"].iter().zip(assemblies) {
+            table = table.rename(column, assembly);
+        }
+        table
+    }
+}
+
+impl Row for OxfordPair {
+    fn columns() -> Vec<Column> {
+        vec![
+            Column::record("category", ColumnType::Text),
+            Column::record("feature_id", ColumnType::Text),
+            Column::record("x", ColumnType::Float),
+            Column::record("y", ColumnType::Float),
+        ]
+    }
+
+    fn record(&self) -> Value {
+        json!({"category": self.category, "feature_id": self.feature_id, "x": self.x, "y": self.y})
+    }
+}
+
+/// A file held by GoaT, from a files report.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileEntry {
+    /// The file name.
+    pub name: String,
+    /// The type of file, e.g. image.
+    pub file_type: String,
+    /// The size in bytes, if known.
+    pub size: Option<u64>,
+    /// Where to download the file from.
+    pub url: String,
+    /// The MD5 checksum, if provided.
+    pub md5: Option<String>,
+    /// The SHA-256 checksum, if provided.
+    pub sha256: Option<String>,
+}
+
+impl FileEntry {
+    /// Get the list of files from a files report. Files without
+    /// a URL are skipped, as they cannot be downloaded.
+    pub fn from_response(response: &Value) -> Result<Vec<Self>> {
+        let body = report_body(response, "files")?;
+        // the list may be nested in a further `files` object
+        let files = match &body["files"] {
+            Value::Array(files) => files,
+            _ => body.as_array().ok_or_else(|| no_data("files"))?,
+        };
+
+        // the first of the keys that is a string
+        let text = |file: &Value, keys: &[&str]| {
+            keys.iter()
+                .find_map(|key| file[*key].as_str())
+                .map(String::from)
+        };
+
+        Ok(files
+            .iter()
+            .filter_map(|file| {
+                let url = text(file, &["url", "download_url"])?;
+                let name = text(file, &["name", "file_name", "filename"])
+                    .or_else(|| url.rsplit('/').next().map(String::from))
+                    .unwrap_or_default();
+                Some(FileEntry {
+                    name,
+                    file_type: text(file, &["type", "file_type", "mime_type"]).unwrap_or_default(),
+                    size: ["size_bytes", "size"]
+                        .iter()
+                        .find_map(|key| file[*key].as_u64()),
+                    url,
+                    md5: text(file, &["md5", "md5sum"]),
+                    sha256: text(file, &["sha256", "sha256sum"]),
+                })
+            })
+            .collect())
+    }
+}
+
+impl Row for FileEntry {
+    fn columns() -> Vec<Column> {
+        vec![
+            Column::record("name", ColumnType::Text),
+            Column::record("type", ColumnType::Text),
+            Column::record("size", ColumnType::Long),
+            Column::record("url", ColumnType::Text),
+        ]
+    }
+
+    fn record(&self) -> Value {
+        json!({"name": self.name, "type": self.file_type, "size": self.size, "url": self.url})
+    }
+}
+
+/// The count of taxa in a country, from a map report.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CountryCount {
+    /// ISO 3166-1 alpha-2 country code.
+    pub iso: String,
+    /// The category, if the report was split by one.
+    pub category: Option<String>,
+    /// The number of taxa.
+    pub count: u64,
+}
+
+impl CountryCount {
+    /// Get the per-country counts from a map report, which are
+    /// keyed by ISO code in `regionCounts`, or in `byCat` for
+    /// each category. Sorted by country, then category.
+    pub fn from_response(response: &Value) -> Result<Vec<Self>> {
+        let body = report_body(response, "map")?;
+        // the counts may be nested in a further `map` object
+        let map = match body["map"].is_object() {
+            true => &body["map"],
+            false => body,
+        };
+
+        let to_counts = |category: Option<&String>, region_counts: &Value| {
+            region_counts
+                .as_object()
+                .into_iter()
+                .flatten()
+                .map(|(iso, count)| CountryCount {
+                    iso: iso.to_uppercase(),
+                    category: category.cloned(),
+                    count: count.as_u64().unwrap_or(0),
+                })
+                .collect::<Vec<CountryCount>>()
+        };
+
+        let mut counts = match (map["byCat"].as_object(), map["regionCounts"].is_object()) {
+            (Some(by_cat), _) => by_cat
+                .iter()
+                .flat_map(|(cat, region_counts)| to_counts(Some(cat), region_counts))
+                .collect(),
+            (None, true) => to_counts(None, &map["regionCounts"]),
+            (None, false) => return Err(no_data("map")),
+        };
+        counts.sort_by(|a, b| a.iso.cmp(&b.iso).then(a.category.cmp(&b.category)));
+
+        Ok(counts)
+    }
+}
+
+impl Row for CountryCount {
+    fn columns() -> Vec<Column> {
+        vec![
+            Column::record("iso", ColumnType::Text),
+            Column::record("category", ColumnType::Text),
+            Column::record("count", ColumnType::Long),
+        ]
+    }
+
+    fn record(&self) -> Value {
+        json!({"iso": self.iso, "category": self.category, "count": self.count})
+    }
+}
+
+/// A node of a Newick tree, as a row of a table.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TreeNode {
+    /// The index of the node, in depth first order from the
+    /// root at 0.
+    pub id: usize,
+    /// The index of the parent node. The root has none.
+    pub parent: Option<usize>,
+    /// The label.
+    pub name: String,
+    /// The branch length, if there is one.
+    pub branch_length: Option<f64>,
+    /// Whether the node is a tip.
+    pub tip: bool,
+}
+
+impl TreeNode {
+    /// The nodes of `tree`, depth first.
+    pub fn from_tree(tree: &Node) -> Vec<Self> {
+        let mut rows = vec![];
+        let mut stack = vec![(tree, None)];
+        while let Some((node, parent)) = stack.pop() {
+            let id = rows.len();
+            rows.push(TreeNode {
+                id,
+                parent,
+                name: node.name.clone(),
+                branch_length: node.length.as_deref().and_then(|l| l.parse().ok()),
+                tip: node.is_tip(),
+            });
+            stack.extend(node.children.iter().rev().map(|child| (child, Some(id))));
+        }
+        rows
+    }
+}
+
+impl Row for TreeNode {
+    fn columns() -> Vec<Column> {
+        vec![
+            Column::record("id", ColumnType::Long),
+            Column::record("parent", ColumnType::Long),
+            Column::record("name", ColumnType::Text),
+            Column::record("branch_length", ColumnType::Float),
+            Column::record("tip", ColumnType::Text),
+        ]
+    }
+
+    fn record(&self) -> Value {
+        json!({
+            "id": self.id,
+            "parent": self.parent,
+            "name": self.name,
+            "branch_length": self.branch_length,
+            "tip": self.tip
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::report::newick;
+
+    fn tsv<R: Row>(rows: &[R]) -> String {
+        table(rows).to_delimited('\t')
+    }
+
+    #[test]
+    fn test_scatter_tsv() {
+        let response = json!({"report": {"scatter": {
+            "status": {"success": true},
+            "histograms": {
+                "buckets": [1, 10, 100],
+                "yBuckets": [0.5, 1.5],
+                "allValues": [[3, 4], [5, 6]]
+            }
+        }}});
+        let tsv = tsv(&ScatterBin::from_response(&response).unwrap());
+        let lines: Vec<&str> = tsv.lines().collect();
+        assert_eq!(lines[0], "x_min\tx_max\ty_min\ty_max\tcount");
+        assert_eq!(lines[1], "1\t10\t0.5\t1.5\t3");
+        assert_eq!(lines[2], "1\t10\t1.5\t\t4");
+        assert_eq!(lines.len(), 5);
+    }
+
+    #[test]
+    fn test_scatter_tsv_with_categories() {
+        let response = json!({"report": {"scatter": {
+            "cats": [{"key": "chromosome", "label": "Chromosome"}],
+            "histograms": {
+                "buckets": [1, 10],
+                "yBuckets": [0, 1],
+                "byCat": {"chromosome": [[7]]}
+            }
+        }}});
+        let tsv = tsv(&ScatterBin::from_response(&response).unwrap());
+        assert_eq!(tsv.lines().nth(1), Some("1\t10\t0\t1\tChromosome\t7"));
+    }
+
+    #[test]
+    fn test_histogram_tsv() {
+        let response = json!({"report": {"table": {
+            "cats": [{"key": "chromosome", "label": "Chromosome"}],
+            "histograms": {
+                "buckets": [0, 10],
+                "byCat": {"chromosome": [2]}
+            }
+        }}});
+        assert_eq!(
+            tsv(&HistogramBin::from_response(&response, "table").unwrap()),
+            "min\tmax\tcategory\tcount\n0\t10\tChromosome\t2"
+        );
+    }
+
+    #[test]
+    fn test_sources_tsv_sorted_by_count() {
+        let response = json!({"report": {"sources": {
+            "status": {"success": true},
+            "NCBI": {"count": 2, "url": "https://www.ncbi.nlm.nih.gov/"},
+            "DToL": {"count": 10, "url": "https://www.darwintreeoflife.org/"},
+            "Kew": {"count": 2}
+        }}});
+        let tsv = tsv(&SourceCount::from_response(&response).unwrap());
+        let lines: Vec<&str> = tsv.lines().collect();
+        assert_eq!(
+            lines,
+            vec![
+                "source\tcount\tlink",
+                "DToL\t10\thttps://www.darwintreeoflife.org/",
+                "Kew\t2\t",
+                "NCBI\t2\thttps://www.ncbi.nlm.nih.gov/",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_arc_tsv() {
+        let response = json!({"report": {"arc": {
+            "x": 12, "y": 48, "arc": 0.25, "rank": "family"
+        }}});
+        assert_eq!(
+            tsv(&[ArcCount::from_response(&response).unwrap()]),
+            "rank\tx\ty\tfraction\nfamily\t12\t48\t0.25"
+        );
+    }
+
+    #[test]
+    fn test_map_tsv() {
+        let response = json!({"report": {"map": {"map": {
+            "regionCounts": {"GB": 3, "fr": 1}
+        }}}});
+        let counts = CountryCount::from_response(&response).unwrap();
+        assert_eq!(tsv(&counts), "iso\tcount\nFR\t1\nGB\t3");
+    }
+
+    #[test]
+    fn test_map_tsv_by_category() {
+        let response = json!({"report": {"map": {
+            "byCat": {
+                "scaffold": {"GB": 2},
+                "chromosome": {"GB": 1, "DE": 4}
+            }
+        }}});
+        let counts = CountryCount::from_response(&response).unwrap();
+        assert_eq!(
+            tsv(&counts),
+            "iso\tcategory\tcount\nDE\tchromosome\t4\nGB\tchromosome\t1\nGB\tscaffold\t2"
+        );
+    }
+
+    #[test]
+    fn test_per_rank_tsv() {
+        let response = json!({"report": {"xPerRank": [
+            {"rank": "family", "x": 12},
+            {"rank": "genus", "x": 80}
+        ]}});
+        assert_eq!(
+            tsv(&RankCount::from_response(&response).unwrap()),
+            "rank\tcount\nfamily\t12\ngenus\t80"
+        );
+    }
+
+    #[test]
+    fn test_files_tsv() {
+        let response = json!({"report": {"files": {"files": [
+            {"name": "Homo_sapiens.jpg", "type": "image", "size_bytes": 20480,
+             "url": "https://example.org/Homo_sapiens.jpg", "md5": "abc"},
+            {"type": "track", "url": "https://example.org/tracks/hs.bw"},
+            {"name": "no_url.txt"}
+        ]}}});
+        let entries = FileEntry::from_response(&response).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].md5.as_deref(), Some("abc"));
+        assert_eq!(entries[1].name, "hs.bw");
+        assert_eq!(
+            tsv(&entries),
+            "name\ttype\tsize\turl\n\
+             Homo_sapiens.jpg\timage\t20480\thttps://example.org/Homo_sapiens.jpg\n\
+             hs.bw\ttrack\t\thttps://example.org/tracks/hs.bw"
+        );
+    }
+
+    #[test]
+    fn test_oxford_tsv() {
+        let response = json!({"report": {"oxford": {"rawData": {
+            "OW569320.1": [
+                {"x": 2000, "y": 150, "featureId": "10012at7088"},
+                {"x": 1000, "y": 900, "featureId": "10003at7088"}
+            ],
+            "OW569319.1": [{"x": 50, "y": 75.5, "featureId": "10100at7088"}]
+        }}}});
+        let assemblies = vec!["GCA_905220365.1".to_string(), "GCA_905147765.1".to_string()];
+        let pairs = OxfordPair::from_response(&response).unwrap();
+        assert_eq!(
+            OxfordPair::table(&pairs, &assemblies).to_delimited('\t'),
+            "category\tfeature_id\tGCA_905220365.1\tGCA_905147765.1\n\
+             OW569319.1\t10100at7088\t50\t75.5\n\
+             OW569320.1\t10003at7088\t1000\t900\n\
+             OW569320.1\t10012at7088\t2000\t150"
+        );
+    }
+
+    #[test]
+    fn test_tree_nodes() {
+        let tree = newick::parse("((A:1,B:2)g1:0.5,C)f1;").unwrap();
+        assert_eq!(
+            tsv(&TreeNode::from_tree(&tree)),
+            "id\tparent\tname\tbranch_length\ttip\n\
+             0\t\tf1\t\tfalse\n\
+             1\t0\tg1\t0.5\tfalse\n\
+             2\t1\tA\t1.0\ttrue\n\
+             3\t1\tB\t2.0\ttrue\n\
+             4\t0\tC\t\ttrue"
+        );
+    }
+
+    #[test]
+    fn test_table_csv_and_json() {
+        let rows = vec![SourceCount {
+            source: "Smith, 2020".into(),
+            count: 1,
+            link: None,
+        }];
+        let table = table(&rows);
+        assert_eq!(
+            table.to_delimited(','),
+            "source,count,link\n\"Smith, 2020\",1,"
+        );
+
+        let mut json = vec![];
+        table.write(OutputFormat::Jsonl, &mut json).unwrap();
+        assert_eq!(
+            String::from_utf8(json).unwrap(),
+            "{\"count\":1,\"link\":null,\"source\":\"Smith, 2020\"}\n"
+        );
+    }
+
+    #[test]
+    fn test_merge_per_taxon_tables() {
+        let aves = table(&[CountryCount {
+            iso: "GB".into(),
+            category: None,
+            count: 3,
+        }]);
+        let mammalia = table(&[CountryCount {
+            iso: "FR".into(),
+            category: Some("chromosome".into()),
+            count: 1,
+        }]);
+        let merged = Table::merge(vec![("Aves".into(), aves), ("Mammalia".into(), mammalia)]);
+        assert_eq!(
+            merged.to_delimited('\t'),
+            "taxon\tiso\tcount\tcategory\nAves\tGB\t3\t\nMammalia\tFR\t1\tchromosome"
+        );
+    }
+
+    #[test]
+    fn test_empty_and_malformed_responses() {
+        let err = parse_json("", ReportType::Arc).unwrap_err();
+        assert!(err.to_string().contains("arc report response was empty"));
+        let err = parse_json("<html>", ReportType::Map).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("map report response was not valid JSON"));
+
+        let response = json!({"report": {"xPerRank": []}});
+        let err = RankCount::from_response(&response).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("no data found for the xPerRank report"));
+    }
+
+    #[test]
+    fn test_scatter_api_error() {
+        let response = json!({"report": {"scatter": {
+            "status": {"success": false, "error": "unknown field"}
+        }}});
+        let err = ScatterBin::from_response(&response).unwrap_err();
+        assert!(err.to_string().contains("unknown field"));
+    }
+}
@@ -0,0 +1,633 @@
+use crate::error::{Error, ErrorKind, Result};
+use crate::report::svg::{escape, PALETTE};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashSet};
+
+/// A node in a Newick tree.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Node {
+    /// The label, unquoted.
+    pub name: String,
+    /// The branch length, kept as written.
+    pub length: Option<String>,
+    /// The child nodes. Tips have none.
+    pub children: Vec<Node>,
+    /// Variable values attached to this node, written as NHX
+    /// or phyloXML properties.
+    pub annotations: Vec<(String, String)>,
+}
+
+/// The characters that end an unquoted label.
+const DELIMITERS: &[char] = &['(', ')', ',', ':', ';', '['];
+
+/// A recursive descent parser over a Newick string.
+struct Parser<'a> {
+    chars: std::iter::Peekable<std::str::Chars<'a>>,
+}
+
+impl<'a> Parser<'a> {
+    /// Skip whitespace and comments, e.g. existing NHX annotations.
+    fn skip(&mut self) {
+        loop {
+            match self.chars.peek() {
+                Some(c) if c.is_whitespace() => {
+                    self.chars.next();
+                }
+                Some('[') => {
+                    for c in self.chars.by_ref() {
+                        if c == ']' {
+                            break;
+                        }
+                    }
+                }
+                _ => return,
+            }
+        }
+    }
+
+    /// A label, which may be quoted with `'`. A quote in a quoted
+    /// label is written `''`.
+    fn label(&mut self) -> String {
+        self.skip();
+        let mut label = String::new();
+        if self.chars.peek() == Some(&'\'') {
+            self.chars.next();
+            while let Some(c) = self.chars.next() {
+                match (c, self.chars.peek()) {
+                    ('\'', Some('\'')) => {
+                        label.push('\'');
+                        self.chars.next();
+                    }
+                    ('\'', _) => break,
+                    (c, _) => label.push(c),
+                }
+            }
+        } else {
+            while let Some(c) = self.chars.peek() {
+                if DELIMITERS.contains(c) || c.is_whitespace() {
+                    break;
+                }
+                label.push(*c);
+                self.chars.next();
+            }
+        }
+        label
+    }
+
+    /// A node and its descendants.
+    fn node(&mut self) -> Result<Node> {
+        let mut node = Node::default();
+        self.skip();
+        if self.chars.peek() == Some(&'(') {
+            self.chars.next();
+            loop {
+                node.children.push(self.node()?);
+                self.skip();
+                match self.chars.next() {
+                    Some(',') => continue,
+                    Some(')') => break,
+                    other => {
+                        return Err(Error::new(ErrorKind::Report(format!(
+                            "could not parse the newick tree, expected ',' or ')' but found {:?}.",
+                            other
+                        ))))
+                    }
+                }
+            }
+        }
+        node.name = self.label();
+        self.skip();
+        if self.chars.peek() == Some(&':') {
+            self.chars.next();
+            let length = self.label();
+            node.length = Some(length);
+        }
+        self.skip();
+        Ok(node)
+    }
+}
+
+/// Parse a Newick string into a tree.
+pub fn parse(newick: &str) -> Result<Node> {
+    let mut parser = Parser {
+        chars: newick.trim().chars().peekable(),
+    };
+    let root = parser.node()?;
+    parser.skip();
+    if parser.chars.peek() == Some(&';') {
+        parser.chars.next();
+        parser.skip();
+    }
+    match parser.chars.next() {
+        None => Ok(root),
+        Some(c) => Err(Error::new(ErrorKind::Report(format!(
+            "could not parse the newick tree, unexpected '{}'.",
+            c
+        )))),
+    }
+}
+
+/// Quote a label if it needs it.
+fn quote(label: &str) -> String {
+    let needs_quotes = label
+        .chars()
+        .any(|c| DELIMITERS.contains(&c) || c == ']' || c == '\'' || c.is_whitespace());
+    match needs_quotes {
+        true => format!("'{}'", label.replace('\'', "''")),
+        false => label.to_string(),
+    }
+}
+
+/// Make a value safe to use in an NHX tag.
+fn nhx_value(value: &str) -> String {
+    value
+        .chars()
+        .map(|c| match c {
+            ':' | '=' | '[' | ']' | ',' | ';' | '(' | ')' => '_',
+            c if c.is_whitespace() => '_',
+            c => c,
+        })
+        .collect()
+}
+
+impl Node {
+    /// Is this node a tip?
+    pub fn is_tip(&self) -> bool {
+        self.children.is_empty()
+    }
+
+    /// All of the tips below (or at) this node.
+    pub fn tips(&self) -> Vec<&Node> {
+        match self.is_tip() {
+            true => vec![self],
+            false => self.children.iter().flat_map(|c| c.tips()).collect(),
+        }
+    }
+
+    /// The first node (depth first) named `name`.
+    fn find_mut(&mut self, name: &str) -> Option<&mut Node> {
+        if self.name == name {
+            return Some(self);
+        }
+        self.children.iter_mut().find_map(|c| c.find_mut(name))
+    }
+
+    /// Visit this node and all of its descendants.
+    fn walk_mut(&mut self, f: &mut impl FnMut(&mut Node)) {
+        f(self);
+        for child in &mut self.children {
+            child.walk_mut(f);
+        }
+    }
+
+    /// Write this node as Newick, without the trailing `;`.
+    /// Annotations are written as NHX comments.
+    fn write_newick(&self, newick: &mut String) {
+        if !self.is_tip() {
+            newick.push('(');
+            for (i, child) in self.children.iter().enumerate() {
+                if i > 0 {
+                    newick.push(',');
+                }
+                child.write_newick(newick);
+            }
+            newick.push(')');
+        }
+        newick.push_str(&quote(&self.name));
+        if let Some(length) = &self.length {
+            newick.push(':');
+            newick.push_str(length);
+        }
+        if !self.annotations.is_empty() {
+            newick.push_str("[&&NHX");
+            for (key, value) in &self.annotations {
+                newick.push_str(&format!(":{}={}", nhx_value(key), nhx_value(value)));
+            }
+            newick.push(']');
+        }
+    }
+
+    /// Remove the tips for which `keep` is false, and any
+    /// internal nodes left without children. Returns whether
+    /// this node is kept.
+    fn retain(&mut self, keep: &impl Fn(&Node) -> bool) -> bool {
+        if self.is_tip() {
+            return keep(self);
+        }
+        self.children.retain_mut(|child| child.retain(keep));
+        !self.children.is_empty()
+    }
+
+    /// Collapse nodes with a single child into that child,
+    /// summing their branch lengths. The child's label is kept,
+    /// as it is the more specific taxon.
+    pub fn collapse_monotypic(&mut self) {
+        for child in &mut self.children {
+            child.collapse_monotypic();
+        }
+        while self.children.len() == 1 {
+            let child = self.children.remove(0);
+            self.length = match (&self.length, &child.length) {
+                (Some(a), Some(b)) => match (a.parse::<f64>(), b.parse::<f64>()) {
+                    (Ok(a), Ok(b)) => Some((a + b).to_string()),
+                    _ => child.length,
+                },
+                (None, length) | (length, None) => length.clone(),
+            };
+            self.name = child.name;
+            self.annotations = child.annotations;
+            self.children = child.children;
+        }
+    }
+
+    /// A copy of the tree with the annotations removed.
+    pub fn without_annotations(&self) -> Node {
+        let mut tree = self.clone();
+        tree.walk_mut(&mut |node| node.annotations.clear());
+        tree
+    }
+
+    /// The tree as a Newick string, annotated with NHX if any
+    /// node has annotations.
+    pub fn to_newick(&self) -> String {
+        let mut newick = String::new();
+        self.write_newick(&mut newick);
+        newick.push(';');
+        newick
+    }
+
+    /// Write this node as a phyloXML clade.
+    fn write_clade(&self, xml: &mut String, depth: usize) {
+        let indent = "  ".repeat(depth);
+        xml.push_str(&format!("{}<clade>\n", indent));
+        if !self.name.is_empty() {
+            xml.push_str(&format!(
+                "{}  <name>{}</name>\n",
+                indent,
+                escape(&self.name)
+            ));
+        }
+        if let Some(length) = &self.length {
+            xml.push_str(&format!(
+                "{}  <branch_length>{}</branch_length>\n",
+                indent,
+                escape(length)
+            ));
+        }
+        for (key, value) in &self.annotations {
+            let datatype = match value.parse::<f64>() {
+                Ok(_) => "xsd:double",
+                Err(_) => "xsd:string",
+            };
+            xml.push_str(&format!(
+                "{}  <property ref=\"goat:{}\" datatype=\"{}\" applies_to=\"clade\">{}</property>\n",
+                indent,
+                escape(key),
+                datatype,
+                escape(value)
+            ));
+        }
+        for child in &self.children {
+            child.write_clade(xml, depth + 1);
+        }
+        xml.push_str(&format!("{}</clade>\n", indent));
+    }
+
+    /// The tree as a phyloXML document, with annotations as
+    /// clade properties.
+    pub fn to_phyloxml(&self) -> String {
+        let mut xml = String::from(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<phyloxml xmlns=\"http://www.phyloxml.org\">\n<phylogeny rooted=\"true\">\n",
+        );
+        self.write_clade(&mut xml, 1);
+        xml.push_str("</phylogeny>\n</phyloxml>");
+        xml
+    }
+}
+
+/// Normalise a label or name for matching tips to search
+/// results, as tree labels may use `_` for spaces.
+fn match_key(name: &str) -> String {
+    name.replace('_', " ").trim().to_lowercase()
+}
+
+/// The value of a variable in a search record as text. Lists,
+/// such as keyword values, are joined with `|`.
+fn record_value(record: &Value, variable: &str) -> Option<String> {
+    match &record["fields"][variable]["value"] {
+        Value::Null => None,
+        Value::String(s) => Some(s.clone()),
+        Value::Array(values) => Some(
+            values
+                .iter()
+                .map(|v| match v {
+                    Value::String(s) => s.clone(),
+                    other => other.to_string(),
+                })
+                .collect::<Vec<String>>()
+                .join("|"),
+        ),
+        other => Some(other.to_string()),
+    }
+}
+
+/// Attach the values of `variables` from search `records` to
+/// the nodes of the tree, matching on scientific name or taxon
+/// ID. Returns the number of nodes annotated.
+pub fn annotate(tree: &mut Node, records: &[Value], variables: &[String]) -> usize {
+    let mut by_name: BTreeMap<String, &Value> = BTreeMap::new();
+    for record in records {
+        if let Some(name) = record["scientific_name"].as_str() {
+            by_name.insert(match_key(name), record);
+        }
+        match &record["taxon_id"] {
+            Value::String(id) => by_name.insert(id.clone(), record),
+            Value::Number(id) => by_name.insert(id.to_string(), record),
+            _ => None,
+        };
+    }
+
+    let mut annotated = 0;
+    tree.walk_mut(&mut |node| {
+        let record = match by_name.get(&match_key(&node.name)) {
+            Some(r) => r,
+            None => return,
+        };
+        node.annotations = variables
+            .iter()
+            .filter_map(|v| record_value(record, v).map(|value| (v.clone(), value)))
+            .collect();
+        if !node.annotations.is_empty() {
+            annotated += 1;
+        }
+    });
+    annotated
+}
+
+/// Merge two trees of the same taxonomy, e.g. from batches of
+/// a large list of taxa.
+///
+/// The trees are joined at the node in one tree named as the
+/// root of the other, and the children of nodes with the same
+/// name are merged. If neither tree contains the root of the
+/// other, they are joined under a new unnamed root, so use
+/// [`merge_at_ancestor`] if their common ancestor is known.
+pub fn merge(mut a: Node, mut b: Node) -> Node {
+    if !a.name.is_empty() && a.name == b.name {
+        for child in b.children {
+            match a
+                .children
+                .iter()
+                .position(|c| !c.name.is_empty() && c.name == child.name)
+            {
+                Some(i) => {
+                    let existing = std::mem::take(&mut a.children[i]);
+                    a.children[i] = merge(existing, child);
+                }
+                None => a.children.push(child),
+            }
+        }
+        return a;
+    }
+    if !b.name.is_empty() {
+        if let Some(node) = a.find_mut(&b.name) {
+            let existing = std::mem::take(node);
+            *node = merge(existing, b);
+            return a;
+        }
+    }
+    if !a.name.is_empty() {
+        if let Some(node) = b.find_mut(&a.name) {
+            let existing = std::mem::take(node);
+            *node = merge(existing, a);
+            return b;
+        }
+    }
+    Node {
+        children: vec![a, b],
+        ..Default::default()
+    }
+}
+
+/// Merge the trees of batches at their common ancestor.
+///
+/// `ancestors` is a tree with the root of each batch as a tip
+/// (see [`Report::ancestors_url`]), so the lineage between the
+/// common ancestor and each batch is kept.
+///
+/// [`Report::ancestors_url`]: crate::report::report::Report::ancestors_url
+pub fn merge_at_ancestor(ancestors: Node, batches: Vec<Node>) -> Node {
+    batches.into_iter().fold(ancestors, merge)
+}
+
+/// The taxon IDs of the search `records`, keyed on their
+/// normalised scientific names.
+pub fn taxon_ids(records: &[Value]) -> BTreeMap<String, String> {
+    records
+        .iter()
+        .filter_map(|record| {
+            let name = record["scientific_name"].as_str()?;
+            let id = match &record["taxon_id"] {
+                Value::String(id) => id.clone(),
+                Value::Number(id) => id.to_string(),
+                _ => return None,
+            };
+            Some((match_key(name), id))
+        })
+        .collect()
+}
+
+/// Keep only the tips named in `keep`, by name or by taxon ID
+/// if the ID is in `taxon_ids`, and drop any internal nodes
+/// left without tips. Returns the number of tips left.
+pub fn prune(tree: &mut Node, keep: &[String], taxon_ids: &BTreeMap<String, String>) -> usize {
+    let keep: HashSet<String> = keep.iter().map(|k| match_key(k)).collect();
+    let is_kept = |node: &Node| {
+        let key = match_key(&node.name);
+        keep.contains(&key) || taxon_ids.get(&key).is_some_and(|id| keep.contains(id))
+    };
+    match tree.retain(&is_kept) {
+        true => tree.tips().len(),
+        false => {
+            tree.children.clear();
+            0
+        }
+    }
+}
+
+/// Relabel the nodes of the tree with their taxon IDs. Nodes
+/// without an ID keep their name.
+pub fn relabel(tree: &mut Node, taxon_ids: &BTreeMap<String, String>) {
+    tree.walk_mut(&mut |node| {
+        if let Some(id) = taxon_ids.get(&match_key(&node.name)) {
+            node.name = id.clone();
+        }
+    });
+}
+
+/// An iTOL dataset for the annotations of `variable` on the
+/// tree. Numeric variables are drawn as a bar chart, and any
+/// other as a colour strip with a legend.
+pub fn itol_dataset(tree: &Node, variable: &str) -> String {
+    let mut values: Vec<(String, String)> = vec![];
+    let mut stack = vec![tree];
+    while let Some(node) = stack.pop() {
+        if let Some((_, value)) = node.annotations.iter().find(|(k, _)| k == variable) {
+            values.push((node.name.clone(), value.clone()));
+        }
+        stack.extend(node.children.iter().rev());
+    }
+
+    let numeric = values.iter().all(|(_, v)| v.parse::<f64>().is_ok());
+    let mut dataset = match numeric {
+        true => format!(
+            "DATASET_SIMPLEBAR\nSEPARATOR TAB\nDATASET_LABEL\t{}\nCOLOR\t{}\nDATA\n",
+            variable, PALETTE[0]
+        ),
+        false => {
+            let mut levels: Vec<&String> = values.iter().map(|(_, v)| v).collect();
+            levels.sort();
+            levels.dedup();
+            let colours: Vec<&str> = (0..levels.len())
+                .map(|i| PALETTE[i % PALETTE.len()])
+                .collect();
+            let mut header = format!(
+                "DATASET_COLORSTRIP\nSEPARATOR TAB\nDATASET_LABEL\t{}\nCOLOR\t{}\nLEGEND_TITLE\t{}\n",
+                variable, PALETTE[0], variable
+            );
+            header += &format!("LEGEND_SHAPES\t{}\n", vec!["1"; levels.len()].join("\t"));
+            header += &format!("LEGEND_COLORS\t{}\n", colours.join("\t"));
+            header += &format!(
+                "LEGEND_LABELS\t{}\n",
+                levels
+                    .iter()
+                    .map(|l| l.as_str())
+                    .collect::<Vec<&str>>()
+                    .join("\t")
+            );
+            header += "DATA\n";
+            // colour each node by its value
+            let coloured: Vec<String> = values
+                .iter()
+                .map(|(_, value)| {
+                    let i = levels.iter().position(|l| *l == value).unwrap_or(0);
+                    format!("{}\t{}", colours[i], value)
+                })
+                .collect();
+            for ((_, value), c) in values.iter_mut().zip(coloured) {
+                *value = c;
+            }
+            header
+        }
+    };
+    for (name, value) in &values {
+        dataset += &format!("{}\t{}\n", name, value);
+    }
+    dataset
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_round_trip() {
+        let newick = "((Homo_sapiens:1.5,'Pan troglodytes':1)Hominini:2,Gorilla)Homininae;";
+        let tree = parse(newick).unwrap();
+        assert_eq!(tree.name, "Homininae");
+        assert_eq!(tree.tips().len(), 3);
+        assert_eq!(tree.children[0].children[1].name, "Pan troglodytes");
+        assert_eq!(tree.to_newick(), newick);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(parse("((A,B);").is_err());
+        assert!(parse("(A,B)C;D").is_err());
+    }
+
+    #[test]
+    fn test_annotate_nhx_and_phyloxml() {
+        let mut tree = parse("(Homo_sapiens,9598,Gorilla);").unwrap();
+        let records = vec![
+            json!({"scientific_name": "Homo sapiens", "taxon_id": "9606",
+                   "fields": {"genome_size": {"value": 3100000000u64},
+                              "assembly_level": {"value": ["chromosome", "complete genome"]}}}),
+            json!({"scientific_name": "Pan troglodytes", "taxon_id": "9598",
+                   "fields": {"genome_size": {"value": 3200000000u64}}}),
+        ];
+        let variables = vec!["genome_size".to_string(), "assembly_level".to_string()];
+        assert_eq!(annotate(&mut tree, &records, &variables), 2);
+        assert_eq!(
+            tree.to_newick(),
+            "(Homo_sapiens[&&NHX:genome_size=3100000000:assembly_level=chromosome|complete_genome],9598[&&NHX:genome_size=3200000000],Gorilla);"
+        );
+
+        let xml = tree.to_phyloxml();
+        assert!(xml.contains(
+            "<property ref=\"goat:genome_size\" datatype=\"xsd:double\" applies_to=\"clade\">3100000000</property>"
+        ));
+        assert!(xml.contains("<name>Gorilla</name>"));
+    }
+
+    #[test]
+    fn test_merge_batches() {
+        let a = parse("((A,B)g1,C)f1;").unwrap();
+        let b = parse("((A,D)g1,E)f1;").unwrap();
+        let merged = merge(a, b);
+        assert_eq!(merged.to_newick(), "((A,B,D)g1,C,E)f1;");
+
+        // the root of one tree is a node of the other
+        let c = parse("(F,G)g2;").unwrap();
+        let merged = merge(parse("((A)g1,(F)g2)f1;").unwrap(), c);
+        assert_eq!(merged.to_newick(), "((A)g1,(F,G)g2)f1;");
+
+        let merged = merge(parse("(A)g1;").unwrap(), parse("(F)g2;").unwrap());
+        assert_eq!(merged.to_newick(), "((A)g1,(F)g2);");
+    }
+
+    #[test]
+    fn test_merge_batches_at_ancestor() {
+        // the batches only share o1, above the root of each
+        let batches = vec![parse("((A,B)g1,C)f1;").unwrap(), parse("(F,G)g2;").unwrap()];
+        let ancestors = parse("((f1)s1,((g2)f2)s2)o1;").unwrap();
+        let merged = merge_at_ancestor(ancestors, batches);
+        assert_eq!(merged.to_newick(), "((((A,B)g1,C)f1)s1,(((F,G)g2)f2)s2)o1;");
+    }
+
+    #[test]
+    fn test_prune_collapse_and_relabel() {
+        let mut tree = parse("(((A:1,B:1)g1:1,C:2)f1:1,(D:1)g2:2)o1;").unwrap();
+        let ids = BTreeMap::from([("a".to_string(), "1".to_string())]);
+        assert_eq!(
+            prune(&mut tree, &["1".into(), "C".into(), "D".into()], &ids),
+            3
+        );
+        assert_eq!(tree.to_newick(), "(((A:1)g1:1,C:2)f1:1,(D:1)g2:2)o1;");
+
+        tree.collapse_monotypic();
+        assert_eq!(tree.to_newick(), "((A:2,C:2)f1:1,D:3)o1;");
+
+        relabel(&mut tree, &ids);
+        assert_eq!(tree.to_newick(), "((1:2,C:2)f1:1,D:3)o1;");
+
+        assert_eq!(prune(&mut tree, &["E".into()], &ids), 0);
+    }
+
+    #[test]
+    fn test_itol_datasets() {
+        let mut tree = parse("(A,B,C);").unwrap();
+        tree.children[0].annotations = vec![("level".into(), "chromosome".into())];
+        tree.children[1].annotations = vec![("level".into(), "contig".into())];
+        let strip = itol_dataset(&tree, "level");
+        assert!(strip.starts_with("DATASET_COLORSTRIP"));
+        assert!(strip.contains("LEGEND_LABELS\tchromosome\tcontig\n"));
+        assert!(strip.ends_with("A\t#1f77b4\tchromosome\nB\t#ff7f0e\tcontig\n"));
+
+        tree.children[0].annotations = vec![("size".into(), "10".into())];
+        let bars = itol_dataset(&tree, "size");
+        assert!(bars.starts_with("DATASET_SIMPLEBAR"));
+        assert!(bars.ends_with("DATA\nA\t10\n"));
+    }
+}
@@ -0,0 +1,262 @@
+use crate::error::{Error, ErrorKind, Result};
+use crate::report::report::Opts;
+use crate::report::models::{cell, report_body};
+use owo_colors::{OwoColorize, Stream::Stdout, Style};
+use serde_json::Value;
+
+/// The widest a bar can be, in characters.
+const BAR_WIDTH: usize = 50;
+
+/// A glyph, and a function to colour it.
+type Segment = (char, fn(Style) -> Style);
+
+/// The glyph and colour for each stacked category, so the
+/// categories can be told apart with or without colour.
+const SEGMENTS: [Segment; 6] = [
+    ('█', |s| s.blue()),
+    ('▓', |s| s.green()),
+    ('▒', |s| s.yellow()),
+    ('░', |s| s.magenta()),
+    ('#', |s| s.cyan()),
+    ('=', |s| s.red()),
+];
+
+/// A bucket bound, shortened with a SI suffix if it is a
+/// large number, e.g. 1500000000 is 1.5G.
+pub(crate) fn bound(value: &Value) -> String {
+    let n = match value.as_f64() {
+        Some(n) => n,
+        None => return cell(value),
+    };
+    let (divisor, suffix) = match n.abs() {
+        a if a >= 1e12 => (1e12, "T"),
+        a if a >= 1e9 => (1e9, "G"),
+        a if a >= 1e6 => (1e6, "M"),
+        a if a >= 1e3 => (1e3, "k"),
+        _ => (1.0, ""),
+    };
+    let scaled = format!("{:.2}", n / divisor);
+    let scaled = scaled.trim_end_matches('0').trim_end_matches('.');
+    format!("{}{}", scaled, suffix)
+}
+
+/// A stacked bar of `counts`, scaled so that `max` fills
+/// [`BAR_WIDTH`].
+fn bar(counts: &[u64], max: u64) -> String {
+    let mut bar = String::new();
+    // round the running total, so the segments sum to the total
+    let mut total = 0;
+    let mut drawn = 0;
+    for (i, count) in counts.iter().enumerate() {
+        total += count;
+        let end = match max {
+            0 => 0,
+            _ => ((total as f64 / max as f64) * BAR_WIDTH as f64).round() as usize,
+        };
+        let (glyph, colour) = SEGMENTS[i % SEGMENTS.len()];
+        let segment = glyph.to_string().repeat(end.saturating_sub(drawn));
+        drawn = drawn.max(end);
+        bar += &segment
+            .if_supports_color(Stdout, |text| text.style(colour(Style::new())))
+            .to_string();
+    }
+    bar
+}
+
+/// The bins of a histogram report, with counts for each
+/// category (or a single set of counts if there are none).
+pub(crate) struct Bins {
+    /// The bucket bounds, with the upper bound of the last
+    /// bin if the API returns it.
+    pub buckets: Vec<Value>,
+    /// The category labels, in the order of `counts`.
+    pub labels: Vec<String>,
+    /// The counts in each bin, for each category.
+    pub counts: Vec<Vec<u64>>,
+}
+
+impl Bins {
+    /// The number of bins.
+    pub fn len(&self) -> usize {
+        self.counts.iter().map(|c| c.len()).max().unwrap_or(0)
+    }
+
+    /// The counts of each category in bin `i`.
+    pub fn bin(&self, i: usize) -> Vec<u64> {
+        self.counts
+            .iter()
+            .map(|c| *c.get(i).unwrap_or(&0))
+            .collect()
+    }
+
+    /// The largest total count of any bin.
+    pub fn max(&self) -> u64 {
+        (0..self.len())
+            .map(|i| self.bin(i).iter().sum())
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+/// Get the bins from the JSON of a histogram report.
+pub(crate) fn histogram_bins(response: &Value, report_name: &str) -> Result<Bins> {
+    let body = report_body(response, report_name)?;
+    let histograms = &body["histograms"];
+    let buckets = histograms["buckets"].as_array().ok_or_else(|| {
+        Error::new(ErrorKind::Report(
+            "no data found for the histogram report.".into(),
+        ))
+    })?;
+
+    let to_counts = |values: &Value| -> Vec<u64> {
+        values
+            .as_array()
+            .into_iter()
+            .flatten()
+            .map(|v| v.as_u64().unwrap_or(0))
+            .collect()
+    };
+
+    // counts per category, in the order of the legend
+    let cats = body["cats"].as_array().filter(|c| !c.is_empty());
+    let (labels, counts) = match cats {
+        Some(cats) => cats
+            .iter()
+            .map(|cat| {
+                let key = cell(&cat["key"]);
+                let counts = to_counts(&histograms["byCat"][&key]);
+                (cat["label"].as_str().map_or(key, String::from), counts)
+            })
+            .unzip(),
+        None => (vec![], vec![to_counts(&histograms["allValues"])]),
+    };
+
+    Ok(Bins {
+        buckets: buckets.clone(),
+        labels,
+        counts,
+    })
+}
+
+/// Render a histogram report as a horizontal bar chart for
+/// the terminal, with a bar per bin. If the report has
+/// categories, the bars are stacked by category.
+///
+/// The bins are made by the API, so they follow the scale in
+/// the x options (e.g. `log10`), which is shown in the title.
+pub fn histogram(response: &Value, report_name: &str, x_opts: Option<&Opts>) -> Result<String> {
+    let body = report_body(response, report_name)?;
+    let bins = histogram_bins(response, report_name)?;
+    let buckets = &bins.buckets;
+    let labels = &bins.labels;
+    let n_bins = bins.len();
+    let max = bins.max();
+
+    let ranges: Vec<String> = (0..n_bins)
+        .map(|i| {
+            let lower = buckets.get(i).map(bound).unwrap_or_default();
+            match buckets.get(i + 1) {
+                Some(upper) => format!("{}-{}", lower, bound(upper)),
+                None => format!("{}+", lower),
+            }
+        })
+        .collect();
+    let width = ranges.iter().map(|r| r.chars().count()).max().unwrap_or(0);
+
+    let mut plot = String::new();
+    let title = x_opts
+        .and_then(|o| o.axis_title.clone())
+        .or_else(|| body["x"].as_str().map(String::from));
+    let scale = x_opts.and_then(|o| o.scale.clone());
+    match (title, scale) {
+        (Some(t), Some(s)) => plot += &format!("{} ({} scale)\n", t, s),
+        (Some(t), None) => plot += &format!("{}\n", t),
+        (None, Some(s)) => plot += &format!("({} scale)\n", s),
+        (None, None) => (),
+    }
+
+    for (i, range) in ranges.iter().enumerate() {
+        let counts = bins.bin(i);
+        plot += &format!(
+            "{:>width$} │{} {}\n",
+            range,
+            bar(&counts, max),
+            counts.iter().sum::<u64>(),
+            width = width
+        );
+    }
+
+    if !labels.is_empty() {
+        let legend = labels
+            .iter()
+            .enumerate()
+            .map(|(i, label)| {
+                let (glyph, colour) = SEGMENTS[i % SEGMENTS.len()];
+                format!(
+                    "{} {}",
+                    glyph.if_supports_color(Stdout, |text| text.style(colour(Style::new()))),
+                    label
+                )
+            })
+            .collect::<Vec<String>>()
+            .join("  ");
+        plot += &legend;
+    }
+
+    Ok(plot.trim_end().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_bound_suffixes() {
+        assert_eq!(bound(&json!(1500000000)), "1.5G");
+        assert_eq!(bound(&json!(2000)), "2k");
+        assert_eq!(bound(&json!(12)), "12");
+        assert_eq!(bound(&json!(1.05)), "1.05");
+        assert_eq!(bound(&json!("2020-01-01")), "2020-01-01");
+    }
+
+    #[test]
+    fn test_bar_scales_to_max() {
+        assert_eq!(bar(&[10], 10).chars().count(), BAR_WIDTH);
+        assert_eq!(bar(&[5], 10).chars().count(), BAR_WIDTH / 2);
+        assert_eq!(bar(&[0], 0), "");
+    }
+
+    #[test]
+    fn test_histogram_plot() {
+        let response = json!({"report": {"table": {
+            "histograms": {
+                "buckets": [1000000, 10000000, 100000000],
+                "allValues": [4, 2]
+            }
+        }}});
+        let opts = Opts::try_from_string(",,,log10,Genome size").unwrap();
+        let plot = histogram(&response, "table", Some(&opts)).unwrap();
+        assert_eq!(histogram_bins(&response, "table").unwrap().max(), 4);
+        let lines: Vec<&str> = plot.lines().collect();
+        assert_eq!(lines[0], "Genome size (log10 scale)");
+        assert!(lines[1].starts_with("  1M-10M │"));
+        assert!(lines[1].ends_with(" 4"));
+        assert!(lines[2].starts_with("10M-100M │"));
+    }
+
+    #[test]
+    fn test_histogram_plot_stacks_categories() {
+        let response = json!({"report": {"table": {
+            "cats": [{"key": "chromosome"}, {"key": "scaffold"}],
+            "histograms": {
+                "buckets": [0, 1],
+                "byCat": {"chromosome": [1], "scaffold": [3]}
+            }
+        }}});
+        let plot = histogram(&response, "table", None).unwrap();
+        let lines: Vec<&str> = plot.lines().collect();
+        assert!(lines[0].ends_with(" 4"));
+        assert!(lines[1].contains("chromosome") && lines[1].contains("scaffold"));
+    }
+}
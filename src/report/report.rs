@@ -1,22 +1,23 @@
 use crate::error::{Error, ErrorKind, Result};
-use crate::utils::variable_data;
-use crate::utils::{tax_ranks::TaxRanks, utils, variables::Variables};
-use crate::{TaxType, GOAT_URL, TAXONOMY};
+use crate::utils::catalogue::variable_data;
+use crate::utils::expression::{TypeOf, Variable};
+use crate::utils::{tax_ranks::TaxRanks, url::format_expression, utils, variables::Variables};
+use crate::{IndexType, TaxType, TAXONOMY, UPPER_CLI_FILE_LIMIT, UPPER_CLI_SIZE_LIMIT};
 use std::fmt;
 use url::Url;
 
 // | Implemented | Report        | Required             | Optional
 // --------------|---------------|-------------------------------------------
-//               | Files         | x                    | checkedFiles
-//               | Histogram     | x                    | cat, catToX, rank, xOpts
-//               | Map           | x                    | cat, rank
-//               | Oxford        | x                    | cat, xOpts, yOpts
+// X             | Files         | x                    | checkedFiles
+// X             | Histogram     | x                    | cat, catToX, rank, xOpts
+// X             | Map           | x                    | cat, rank
+// X             | Oxford        | x                    | cat, xOpts, yOpts
 // X             | Scatter       | x, y, rank           | cat, xOpts, yOpts, scatterThreshold
 // X             | Table         | x, y                 | cat, rank, xOpts, yOpts, scatterThreshold
 // X             | Sources       | -                    | -
 // X (as newick) | Tree          | x                    | y, cat, xOpts, yOpts, collapseMonotypic, treeThreshold
 // X             | arc           | x, rank              | y
-//               | xPerRank      | x                    | ranks
+// X             | xPerRank      | x                    | ranks
 
 // Search related parameters fields, includeEstimates, exclude* and queryId are optional for all reports (except sources where they have no effect).
 
@@ -35,10 +36,20 @@ pub enum ReportType {
     Histogram,
     /// A scatterplot, requiring two variables.
     Scatterplot,
-    /// Arc
+    /// Arc, the fraction of taxa at a rank matching
+    /// one query (x) out of those matching another (y).
     Arc,
     /// Sources
     Sources,
+    /// A map of counts per country.
+    Map,
+    /// The number of taxa at each of a list of ranks.
+    PerRank,
+    /// An Oxford plot, the positions of features shared by
+    /// two assemblies.
+    Oxford,
+    /// The files (e.g. images) held for the taxa.
+    Files,
 }
 
 impl fmt::Display for ReportType {
@@ -50,6 +61,12 @@ impl fmt::Display for ReportType {
         match self {
             ReportType::Newick => write!(f, "tree"),
             ReportType::Sources => write!(f, "sources"),
+            ReportType::Scatterplot => write!(f, "scatter"),
+            ReportType::Arc => write!(f, "arc"),
+            ReportType::Map => write!(f, "map"),
+            ReportType::PerRank => write!(f, "xPerRank"),
+            ReportType::Oxford => write!(f, "oxford"),
+            ReportType::Files => write!(f, "files"),
             _ => write!(f, "table"),
         }
     }
@@ -58,13 +75,18 @@ impl fmt::Display for ReportType {
 /// The x or y options for a returned table.
 ///
 /// Argh these are going to be annoying to parse.
-#[derive(Default, Debug)]
+#[derive(Default, Debug, Clone)]
 pub struct Opts {
-    min: Option<i32>,
-    max: Option<i32>,
-    tick_count: Option<i32>,
-    scale: Option<String>,
-    axis_title: Option<String>,
+    /// The minimum value of the axis.
+    pub min: Option<i32>,
+    /// The maximum value of the axis.
+    pub max: Option<i32>,
+    /// The number of ticks, or bins.
+    pub tick_count: Option<i32>,
+    /// The scale, one of [`Opts::SCALE_TYPES`].
+    pub scale: Option<String>,
+    /// The title of the axis.
+    pub axis_title: Option<String>,
 }
 
 impl Opts {
@@ -228,16 +250,38 @@ impl fmt::Display for Opts {
             None => "".into(),
         };
 
-        write!(
-            f,
-            "{}",
-            [min, max, tick_count, scale, axis_title].join(",")
-        )
+        write!(f, "{}", [min, max, tick_count, scale, axis_title].join(","))
+    }
+}
+
+/// Parse the category of a report, which should be a taxon
+/// rank (e.g. phylum) or a keyword variable (e.g. assembly_level).
+fn parse_category(category: &str, index_type: IndexType) -> Result<String> {
+    if let Ok(rank) = TaxRanks::init().parse(category, true) {
+        return Ok(rank);
+    }
+    match variable_data(index_type).get(category) {
+        Some(Variable {
+            type_of: TypeOf::Keyword(_),
+            ..
+        }) => Ok(category.to_string()),
+        Some(_) => Err(Error::new(ErrorKind::Report(format!(
+            "\"{}\" is not a keyword variable, so cannot be used as a category.",
+            category
+        )))),
+        None => {
+            // suggest a variable, if one is close
+            Variables::new(category).parse_one(variable_data(index_type))?;
+            Err(Error::new(ErrorKind::Report(format!(
+                "the category \"{}\" should be a taxon rank or a keyword variable.",
+                category
+            ))))
+        }
     }
 }
 
 /// The record struct to make URLs from.
-#[derive(Default)]
+#[derive(Default, Clone)]
 pub struct Report {
     /// The type of the report; tree or table.
     pub report_type: ReportType,
@@ -246,67 +290,125 @@ pub struct Report {
     /// The rank of the return type.
     /// Default from CLI is species.
     pub rank: String,
+    /// The ranks to count taxa at, for xPerRank.
+    pub ranks: Vec<String>,
     /// Taxon type: tax_tree or tax_name
     pub taxon_type: TaxType,
+    /// The index to report on, taxon or assembly.
+    pub index_type: IndexType,
     /// The size of the result to return
     pub size: Option<usize>,
     // these below are optional extras, which are
     // needed for some report return types.
-    /// The x value
+    /// The x value. For Arc, Map and xPerRank, this is a parsed
+    /// expression.
     pub x: Option<String>,
-    /// The y value. Required for Scatterplot.
+    /// The y value. Required for Scatterplot, optional
+    /// expression for Arc.
     pub y: Option<String>,
     /// x options. Always optional.
     pub x_opts: Option<Opts>,
     /// The y options. Always optional.
     pub y_opts: Option<Opts>,
-    /// The category. Required for CategoricalHistogram.
+    /// The category, a taxon rank or keyword variable to split
+    /// a histogram by.
     pub category: Option<String>,
-    /// The threshold. For Newick.
+    /// Put the categories of a histogram on the x axis.
+    pub cat_to_x: bool,
+    /// The threshold. `treeThreshold` for Newick, and
+    /// `scatterThreshold` for Scatterplot.
     pub threshold: i32,
+    /// The GoaT API URL to build the report against.
+    pub goat_url: String,
+    /// Variables to annotate the tips of a Newick tree with.
+    pub variables: Vec<String>,
+    /// The type of feature to compare assemblies by, for Oxford.
+    pub feature_type: Option<String>,
+    /// Make a report for each taxon in [`Report::search`],
+    /// instead of one for all of them.
+    pub per_taxon: bool,
 }
 
 impl Report {
     /// Constructor function for [`Report`].
-    pub fn new(matches: &clap::ArgMatches, report_type: ReportType) -> Result<Self> {
+    pub fn new(
+        matches: &clap::ArgMatches,
+        report_type: ReportType,
+        index_type: IndexType,
+    ) -> Result<Self> {
         // create the default struct
         let mut report: Report = Report {
             report_type,
+            index_type,
+            goat_url: utils::goat_url(matches)?,
             ..Default::default()
         };
 
         // fill the mandatory fields.
-        // search from CLI
-        let search = matches
-            .get_one::<String>("taxon")
-            .expect("cli requires input");
-        // TODO: could also take from file
-        report.search = utils::parse_comma_separated(search);
-
-        // safe to unwrap, as default is defined.
+        // search from CLI, or from a file. Oxford plots compare
+        // two assemblies instead.
+        report.search = match matches.try_get_one::<String>("assemblies").ok().flatten() {
+            Some(assemblies) => utils::parse_comma_separated(assemblies),
+            None => utils::report_taxa(matches)?,
+        };
+        report.feature_type = matches
+            .try_get_one::<String>("feature-type")
+            .ok()
+            .flatten()
+            .cloned();
+
+        // the default is defined, except for xPerRank which
+        // takes a list of ranks instead.
         report.rank = matches
-            .get_one::<String>("rank")
-            .expect("cli default = species")
-            .to_string();
+            .try_get_one::<String>("rank")
+            .ok()
+            .flatten()
+            .map_or("species".into(), |e| e.to_string());
+        if let Some(ranks) = matches.try_get_one::<String>("ranks").ok().flatten() {
+            let tax_ranks = TaxRanks::init();
+            report.ranks = utils::parse_comma_separated(ranks)
+                .iter()
+                .map(|rank| tax_ranks.parse(rank, true))
+                .collect::<Result<Vec<String>>>()?;
+        }
         // taxon type will be by default tax_tree(). change this here
         // for future reference. But will require a flag on the cli.
 
-        // for newick
-        let threshold = matches
-            .get_one::<i32>("threshold")
-            .expect("cli default 2000");
-        report.threshold = *threshold;
+        // for newick and scatter; not every report has a threshold.
+        if let Some(threshold) = matches.try_get_one::<i32>("threshold").ok().flatten() {
+            report.threshold = *threshold;
+        }
 
         // the x string will be just a variable.
         let x_variable = matches.get_one::<String>("x-variable");
 
         if let Some(xvar) = x_variable {
-            let inner_x =
-                Variables::new(xvar).parse_one(&variable_data::GOAT_TAXON_VARIABLE_DATA)?;
+            let inner_x = Variables::new(xvar).parse_one(variable_data(index_type))?;
             // assign to struct
             report.x = Some(inner_x);
         };
 
+        // the arc report takes expressions for x and y instead.
+        let x_expression = matches.try_get_one::<String>("x-expression").ok().flatten();
+        if let Some(xexp) = x_expression {
+            report.x = Some(format_expression(xexp, index_type)?);
+        }
+        let y_expression = matches.try_get_one::<String>("y-expression").ok().flatten();
+        if let Some(yexp) = y_expression {
+            report.y = Some(format_expression(yexp, index_type)?);
+        }
+        // as do the map reports, with a single expression.
+        let expression = matches.try_get_one::<String>("expression").ok().flatten();
+        if let Some(exp) = expression {
+            report.x = Some(format_expression(exp, index_type)?);
+        }
+
+        // variables to annotate a newick with.
+        if let Some(vars) = matches.try_get_one::<String>("variables").ok().flatten() {
+            let parsed = Variables::new(vars).parse(variable_data(index_type), false)?;
+            report.variables = utils::parse_comma_separated(&parsed);
+        }
+
         // parse size
         let size = matches.get_one::<usize>("size");
         report.size = size.copied();
@@ -323,7 +425,8 @@ impl Report {
         // now the optionals.
         let y_variable = matches.get_one::<String>("y-variable");
         if let Some(y_var) = y_variable {
-            report.y = Some(y_var.to_string());
+            let inner_y = Variables::new(y_var).parse_one(variable_data(index_type))?;
+            report.y = Some(inner_y);
         }
         // x options
         let xopts = matches.get_one::<String>("x-opts");
@@ -335,45 +438,129 @@ impl Report {
         if let Some(y_opts) = yopts {
             report.y_opts = Some(Opts::try_from_string(y_opts)?);
         }
-        // category for histogram.
-        let category = matches.get_one::<String>("category");
-        if let Some(cat) = category {
-            // FIXME: is this correct? Looks a bit wrong
-
-            // check this variable against the various lists
-            let parsed_taxon_rank = TaxRanks::parse(&TaxRanks::init(), cat, true).ok();
-            let parsed_category = Variables::new(cat)
-                .parse_one(&variable_data::GOAT_TAXON_VARIABLE_DATA)
-                .ok();
-
-            match parsed_taxon_rank {
-                Some(tr) => {
-                    report.category = Some(tr);
-                    // kinda janky but works for now.
-                    return Ok(report);
-                }
-                None => {
-                    // propagate this error through
-                    match parsed_category {
-                        Some(pc) => {
-                            report.category = Some(pc);
-                            return Ok(report);
-                        }
-                        None => {
-                            // TODO: this is quite a brutally large error, but can't think
-                            // right now how to make it nicer.
-                            return Err(Error::new(ErrorKind::Report(
-                                "neither taxon rank or category specified".into(),
-                            )));
-                        }
-                    }
-                }
-            }
+        // category for histogram, split into the --size most
+        // common values.
+        if let Some(cat) = matches.try_get_one::<String>("category").ok().flatten() {
+            report.category = Some(parse_category(cat, index_type)?);
         }
+        report.per_taxon = matches
+            .try_get_one::<bool>("per-taxon")
+            .ok()
+            .flatten()
+            .copied()
+            .unwrap_or(false);
+        report.cat_to_x = matches
+            .try_get_one::<bool>("cat-to-x")
+            .ok()
+            .flatten()
+            .copied()
+            .unwrap_or(false);
 
         Ok(report)
     }
 
+    /// The `report` endpoint of the GoaT API that this report
+    /// is built against.
+    fn report_base(&self) -> String {
+        format!("{}report", self.goat_url)
+    }
+
+    /// The search URLs for the taxon IDs, and the values of
+    /// [`Report::variables`], of the taxa at the tips of a
+    /// Newick tree. There is a URL for each batch of taxa.
+    pub fn search_urls(&self, unique_ids: &[String]) -> Vec<String> {
+        self.search
+            .chunks(*UPPER_CLI_FILE_LIMIT)
+            .zip(unique_ids)
+            .map(|(taxa, unique_id)| self.search_url(taxa, unique_id))
+            .collect()
+    }
+
+    /// The search URL for a single batch of taxa.
+    fn search_url(&self, taxa: &[String], unique_id: &str) -> String {
+        let base = format!("{}search", self.goat_url);
+        let mut url = Url::parse(&base).expect("goat_url is a valid base");
+        let taxa = taxa.join(",");
+        let size = match self.threshold {
+            t if t > 0 => (t as usize).min(*UPPER_CLI_SIZE_LIMIT),
+            _ => *UPPER_CLI_SIZE_LIMIT,
+        };
+        url.query_pairs_mut()
+            .append_pair(
+                "query",
+                &format!("tax_tree({}) AND tax_rank({})", taxa, self.rank),
+            )
+            .append_pair("result", &self.index_type.to_string());
+        if !self.variables.is_empty() {
+            url.query_pairs_mut()
+                .append_pair("fields", &self.variables.join(","));
+        }
+        url.query_pairs_mut()
+            .append_pair("size", &size.to_string())
+            .append_pair("includeEstimates", "true")
+            .append_pair("taxonomy", &TAXONOMY)
+            .append_pair("queryId", &format!("goat_cli_{}", unique_id));
+        url.to_string()
+    }
+
+    /// The report URL for a Newick tree of `taxa`.
+    fn newick_url(&self, taxa: &[String], unique_id: &str) -> String {
+        // join multiple taxa with plain comma; url builder will percent-encode it
+        let csqs = taxa.join(",");
+        let x_value = format!("tax_rank({}) AND tax_tree({})", self.rank, csqs);
+        self.tree_url(&x_value, unique_id)
+    }
+
+    /// The report URL for a Newick tree with the roots of the
+    /// batch trees as tips, so the batches can be joined at
+    /// their common ancestor.
+    pub fn ancestors_url(&self, roots: &[String], unique_id: &str) -> String {
+        self.tree_url(&format!("tax_name({})", roots.join(",")), unique_id)
+    }
+
+    /// The report URL for a Newick tree of the taxa matching
+    /// `x_value`.
+    fn tree_url(&self, x_value: &str, unique_id: &str) -> String {
+        let mut url = Url::parse(&self.report_base()).expect("goat_url is a valid base");
+        url.query_pairs_mut()
+            .append_pair("result", &self.index_type.to_string())
+            .append_pair("report", &self.report_type.to_string())
+            .append_pair("x", x_value)
+            .append_pair("treeThreshold", &self.threshold.to_string())
+            .append_pair("includeEstimates", "true")
+            .append_pair("taxonomy", &TAXONOMY)
+            .append_pair("queryId", &format!("goat_cli_{}", unique_id));
+        url.to_string()
+    }
+
+    /// The report URLs. Newick trees of more than
+    /// [`UPPER_CLI_FILE_LIMIT`] taxa are split into a request
+    /// per batch, with a unique ID each, to be merged after.
+    /// Reports made [`Report::per_taxon`] have a URL per taxon.
+    pub fn make_urls(&self, unique_ids: Vec<String>) -> Result<Vec<String>> {
+        match self.report_type {
+            ReportType::Newick => Ok(self
+                .search
+                .chunks(*UPPER_CLI_FILE_LIMIT)
+                .zip(&unique_ids)
+                .map(|(taxa, unique_id)| self.newick_url(taxa, unique_id))
+                .collect()),
+            _ if self.per_taxon => self
+                .search
+                .iter()
+                .zip(unique_ids)
+                .map(|(taxon, unique_id)| {
+                    let report = Report {
+                        search: vec![taxon.clone()],
+                        ..self.clone()
+                    };
+                    report.make_url(vec![unique_id])
+                })
+                .collect(),
+            _ => Ok(vec![self.make_url(unique_ids)?]),
+        }
+    }
+
     /// A function to construct the report URL for any kind of
     /// report.
     pub fn make_url(&self, unique_ids: Vec<String>) -> Result<String> {
@@ -381,26 +568,7 @@ impl Report {
             ReportType::None => Err(Error::new(ErrorKind::Report(
                 "No report type specified.".into(),
             ))),
-            ReportType::Newick => {
-                let base = format!("{}report", *GOAT_URL);
-                let mut url = Url::parse(&base).expect("GOAT_URL is a valid base");
-                // join multiple taxa with plain comma; url builder will percent-encode it
-                let csqs = match self.search.len() {
-                    1 => self.search[0].clone(),
-                    _ => self.search.join(","),
-                };
-                let x_value =
-                    format!("tax_rank({}) AND tax_tree({})", self.rank, csqs);
-                url.query_pairs_mut()
-                    .append_pair("result", "taxon")
-                    .append_pair("report", &self.report_type.to_string())
-                    .append_pair("x", &x_value)
-                    .append_pair("treeThreshold", &self.threshold.to_string())
-                    .append_pair("includeEstimates", "true")
-                    .append_pair("taxonomy", &TAXONOMY)
-                    .append_pair("queryId", &format!("goat_cli_{}", unique_ids[0]));
-                Ok(url.to_string())
-            }
+            ReportType::Newick => Ok(self.newick_url(&self.search, &unique_ids[0])),
             // Report      | Required             | Optional
             // ------------|----------------------|-------------------------------------------
             // Histogram   | x                    | cat, catToX, rank, xOpts
@@ -413,76 +581,240 @@ impl Report {
                         "Histogram requires an x variable (--x-variable).".into(),
                     ))
                 })?;
-                let cat = self.category.as_deref().ok_or_else(|| {
-                    Error::new(ErrorKind::Report(
-                        "Histogram requires a category (--category).".into(),
-                    ))
-                })?;
-                let size = self.size.ok_or_else(|| {
+                // the category is optional, but needs a size.
+                let cat_value = match (self.category.as_deref(), self.size) {
+                    (Some(cat), Some(size)) => Some(format!("{}[{}]", cat, size)),
+                    (Some(_), None) => {
+                        return Err(Error::new(ErrorKind::Report(
+                            "Histogram requires a size (--size) for the category.".into(),
+                        )))
+                    }
+                    (None, _) => None,
+                };
+
+                let x_value = format!("{}({}) AND {}", taxon_type, taxa, variable);
+
+                let mut url = Url::parse(&self.report_base()).expect("goat_url is a valid base");
+                url.query_pairs_mut()
+                    .append_pair("result", &self.index_type.to_string())
+                    .append_pair("includeEstimates", "true")
+                    .append_pair("taxonomy", &TAXONOMY)
+                    .append_pair("report", &self.report_type.to_string())
+                    .append_pair("rank", &self.rank)
+                    .append_pair("x", &x_value);
+                if let Some(cat) = &cat_value {
+                    url.query_pairs_mut().append_pair("cat", cat);
+                    if self.cat_to_x {
+                        url.query_pairs_mut().append_pair("catToX", "true");
+                    }
+                }
+                if let Some(xopts) = &self.x_opts {
+                    url.query_pairs_mut()
+                        .append_pair("xOpts", &xopts.to_string());
+                }
+                Ok(url.to_string())
+            }
+            // Report      | Required             | Optional
+            // ------------|----------------------|-------------------------------------------
+            // Scatter     | x, y, rank           | cat, xOpts, yOpts, scatterThreshold
+            ReportType::Scatterplot => {
+                let taxa = self.search.join(",");
+                let (x_variable, y_variable) = match (&self.x, &self.y) {
+                    (Some(x), Some(y)) => (x, y),
+                    _ => {
+                        return Err(Error::new(ErrorKind::Report(
+                            "Scatter plots require an x and a y variable (--x-variable, --y-variable).".into(),
+                        )))
+                    }
+                };
+
+                let x_value = format!("{}({}) AND {}", self.taxon_type, taxa, x_variable);
+
+                let mut url = Url::parse(&self.report_base()).expect("goat_url is a valid base");
+                url.query_pairs_mut()
+                    .append_pair("result", &self.index_type.to_string())
+                    .append_pair("includeEstimates", "true")
+                    .append_pair("taxonomy", &TAXONOMY)
+                    .append_pair("report", &self.report_type.to_string())
+                    .append_pair("rank", &self.rank)
+                    .append_pair("x", &x_value)
+                    .append_pair("y", y_variable)
+                    .append_pair("scatterThreshold", &self.threshold.to_string())
+                    .append_pair("queryId", &format!("goat_cli_{}", unique_ids[0]));
+                if let Some(cat) = &self.category {
+                    let cat_value = match self.size {
+                        Some(size) => format!("{}[{}]", cat, size),
+                        None => cat.clone(),
+                    };
+                    url.query_pairs_mut().append_pair("cat", &cat_value);
+                }
+                if let Some(xopts) = &self.x_opts {
+                    url.query_pairs_mut()
+                        .append_pair("xOpts", &xopts.to_string());
+                }
+                if let Some(yopts) = &self.y_opts {
+                    url.query_pairs_mut()
+                        .append_pair("yOpts", &yopts.to_string());
+                }
+                Ok(url.to_string())
+            }
+            // Report      | Required             | Optional
+            // ------------|----------------------|-------------------------------------------
+            // arc         | x, rank              | y
+            ReportType::Arc => {
+                let taxa = format!("{}({})", self.taxon_type, self.search.join(","));
+                let x_expression = self.x.as_deref().ok_or_else(|| {
                     Error::new(ErrorKind::Report(
-                        "Histogram requires a size (--size).".into(),
+                        "Arc reports require an x expression (--x-expression).".into(),
                     ))
                 })?;
+                // by default, y is all of the taxa at this rank.
+                let y_expression = self.y.as_deref().unwrap_or("");
 
-                let x_value = format!("{}({}) AND {}", taxon_type, taxa, variable);
-                let cat_value = format!("{}[{}]", cat, size);
+                let mut url = Url::parse(&self.report_base()).expect("goat_url is a valid base");
+                url.query_pairs_mut()
+                    .append_pair("result", &self.index_type.to_string())
+                    .append_pair("includeEstimates", "true")
+                    .append_pair("taxonomy", &TAXONOMY)
+                    .append_pair("report", &self.report_type.to_string())
+                    .append_pair("rank", &self.rank)
+                    .append_pair("x", &format!("{}{}", taxa, x_expression))
+                    .append_pair("y", &format!("{}{}", taxa, y_expression))
+                    .append_pair("queryId", &format!("goat_cli_{}", unique_ids[0]));
+                Ok(url.to_string())
+            }
+            // Report      | Required             | Optional
+            // ------------|----------------------|-------------------------------------------
+            // Map         | x                    | cat, rank
+            ReportType::Map => {
+                let x_value = format!(
+                    "{}({}){}",
+                    self.taxon_type,
+                    self.search.join(","),
+                    self.x.as_deref().unwrap_or("")
+                );
 
-                let base = format!("{}report", *GOAT_URL);
-                let mut url = Url::parse(&base).expect("GOAT_URL is a valid base");
+                let mut url = Url::parse(&self.report_base()).expect("goat_url is a valid base");
                 url.query_pairs_mut()
-                    .append_pair("result", "taxon")
+                    .append_pair("result", &self.index_type.to_string())
                     .append_pair("includeEstimates", "true")
                     .append_pair("taxonomy", &TAXONOMY)
                     .append_pair("report", &self.report_type.to_string())
                     .append_pair("rank", &self.rank)
                     .append_pair("x", &x_value)
-                    .append_pair("cat", &cat_value);
+                    .append_pair("regionField", "country_list")
+                    .append_pair("queryId", &format!("goat_cli_{}", unique_ids[0]));
+                if let Some(cat) = &self.category {
+                    let cat_value = match self.size {
+                        Some(size) => format!("{}[{}]", cat, size),
+                        None => cat.clone(),
+                    };
+                    url.query_pairs_mut().append_pair("cat", &cat_value);
+                }
+                Ok(url.to_string())
+            }
+            // Report      | Required             | Optional
+            // ------------|----------------------|-------------------------------------------
+            // xPerRank    | x                    | ranks
+            ReportType::PerRank => {
+                let x_value = format!(
+                    "{}({}){}",
+                    self.taxon_type,
+                    self.search.join(","),
+                    self.x.as_deref().unwrap_or("")
+                );
+
+                let mut url = Url::parse(&self.report_base()).expect("goat_url is a valid base");
+                url.query_pairs_mut()
+                    .append_pair("result", &self.index_type.to_string())
+                    .append_pair("includeEstimates", "true")
+                    .append_pair("taxonomy", &TAXONOMY)
+                    .append_pair("report", &self.report_type.to_string())
+                    .append_pair("x", &x_value)
+                    .append_pair("queryId", &format!("goat_cli_{}", unique_ids[0]));
+                if !self.ranks.is_empty() {
+                    url.query_pairs_mut()
+                        .append_pair("ranks", &self.ranks.join(","));
+                }
+                Ok(url.to_string())
+            }
+            // Report      | Required             | Optional
+            // ------------|----------------------|-------------------------------------------
+            // Files       | x                    | checkedFiles
+            ReportType::Files => {
+                let taxa = self.search.join(",");
+                let x_value = format!(
+                    "{}({}){}",
+                    self.taxon_type,
+                    taxa,
+                    self.x.as_deref().unwrap_or("")
+                );
+
+                let mut url = Url::parse(&self.report_base()).expect("goat_url is a valid base");
+                url.query_pairs_mut()
+                    .append_pair("result", &self.index_type.to_string())
+                    .append_pair("report", &self.report_type.to_string())
+                    .append_pair("x", &x_value)
+                    .append_pair("includeEstimates", "false")
+                    .append_pair("taxonomy", &TAXONOMY)
+                    .append_pair("queryId", &format!("goat_cli_{}", unique_ids[0]));
+                Ok(url.to_string())
+            }
+            // Oxford      | x                    | cat, xOpts, yOpts
+            ReportType::Oxford => {
+                let assemblies = match self.search.as_slice() {
+                    [a, b] => format!("{},{}", a, b),
+                    _ => {
+                        return Err(Error::new(ErrorKind::Report(
+                            "Oxford plots compare exactly two assemblies, e.g. -a GCA_905220365.1,GCA_905147765.1.".into(),
+                        )))
+                    }
+                };
+                let x_value = format!(
+                    "assembly_id={} AND feature_type={}",
+                    assemblies,
+                    self.feature_type.as_deref().unwrap_or("busco-gene")
+                );
+
+                let mut url = Url::parse(&self.report_base()).expect("goat_url is a valid base");
+                // the features are in their own index, not the assembly index
+                url.query_pairs_mut()
+                    .append_pair("result", "feature")
+                    .append_pair("report", &self.report_type.to_string())
+                    .append_pair("x", &x_value)
+                    .append_pair("cat", "assembly_id")
+                    .append_pair("includeEstimates", "false")
+                    .append_pair("taxonomy", &TAXONOMY)
+                    .append_pair("queryId", &format!("goat_cli_{}", unique_ids[0]));
                 if let Some(xopts) = &self.x_opts {
                     url.query_pairs_mut()
                         .append_pair("xOpts", &xopts.to_string());
                 }
+                if let Some(yopts) = &self.y_opts {
+                    url.query_pairs_mut()
+                        .append_pair("yOpts", &yopts.to_string());
+                }
                 Ok(url.to_string())
             }
-            ReportType::Scatterplot => Err(Error::new(ErrorKind::Report(
-                "Scatter plots are not yet implemented; please check back in the future!".into(),
-            ))),
-            ReportType::Arc => Err(Error::new(ErrorKind::Report(
-                "Arc reports are not yet implemented; please check back in the future!".into(),
-            ))),
+            // Sources     | -                    | -
             ReportType::Sources => {
-                Err(Error::new(ErrorKind::Report(
-                    "Sources are not yet implemented; please check back in the future!".into(),
-                )))
-                // https://goat.genomehubs.org/reporturl?
-                // query=tax_tree%2891896%5BOrobanchaceae%5D%29
-                // &includeEstimates=false
-                // &includeRawValues=false
-                // &summaryValues=count
-                // &result=taxon
-                // &taxonomy=ncbi
-                // &size=50
-                // &queryId=goat_cli_du8TOca5YBVF4rL
-                // &report=sources
-
-                // this would work, but it's unimplemented yet
-                // let mut url = String::new();
-                // url += &GOAT_URL;
-                // // it's a taxon report
-                // url += "report?result=taxon";
-                // url += "&includeEstimates=false";
-                // url += "&includeRawValues=false";
-                // // standard taxonomy
-                // url += &format!("&taxonomy={}", &*TAXONOMY);
-                // // it's a source
-                // url += &format!("&report={}", self.report_type);
-                // // taxon type: tax_rank/tax_tree
-                // let taxon_type = self.taxon_type;
-                // // and the taxa
-                // let taxa = self.search.join("%2C");
-                // url += &format!("&query={}%28{}%29", taxon_type, taxa);
-                // // add unique_id?
-
-                // Ok(url)
+                let taxa = self.search.join(",");
+                let query = format!(
+                    "{}({}) AND tax_rank({})",
+                    self.taxon_type, taxa, self.rank
+                );
+
+                let mut url = Url::parse(&self.report_base()).expect("goat_url is a valid base");
+                url.query_pairs_mut()
+                    .append_pair("result", &self.index_type.to_string())
+                    .append_pair("includeEstimates", "false")
+                    .append_pair("includeRawValues", "false")
+                    .append_pair("summaryValues", "count")
+                    .append_pair("taxonomy", &TAXONOMY)
+                    .append_pair("report", &self.report_type.to_string())
+                    .append_pair("query", &query)
+                    .append_pair("queryId", &format!("goat_cli_{}", unique_ids[0]));
+                Ok(url.to_string())
             }
         }
     }
@@ -491,6 +823,7 @@ impl Report {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::GOAT_URL;
 
     fn base_report(report_type: ReportType) -> Report {
         Report {
@@ -498,6 +831,7 @@ mod tests {
             search: vec!["Homo sapiens".into()],
             rank: "species".into(),
             taxon_type: TaxType::Tree,
+            goat_url: GOAT_URL.clone(),
             ..Default::default()
         }
     }
@@ -513,13 +847,14 @@ mod tests {
     }
 
     #[test]
-    fn test_histogram_missing_category_returns_err() {
+    fn test_histogram_without_category_has_no_cat() {
         let mut r = base_report(ReportType::Histogram);
-        r.x = Some("genome_size".into());
+        r.x = Some("contig_n50".into());
         r.size = Some(10);
-        let result = r.make_url(vec!["test_id".into()]);
-        assert!(result.is_err());
-        assert!(result.unwrap_err().to_string().contains("category"));
+        r.index_type = IndexType::Assembly;
+        let url = r.make_url(vec!["test_id".into()]).unwrap();
+        assert!(url.contains("result=assembly"));
+        assert!(!url.contains("cat="));
     }
 
     #[test]
@@ -533,14 +868,64 @@ mod tests {
     }
 
     #[test]
-    fn test_arc_returns_err() {
+    fn test_histogram_category_and_cat_to_x() {
+        let mut r = base_report(ReportType::Histogram);
+        r.x = Some("genome_size".into());
+        r.category = Some("phylum".into());
+        r.size = Some(5);
+        r.cat_to_x = true;
+        let url = r.make_url(vec!["test_id".into()]).unwrap();
+        assert!(url.contains("cat=phylum%5B5%5D"));
+        assert!(url.contains("catToX=true"));
+    }
+
+    #[test]
+    fn test_parse_category() {
+        assert_eq!(parse_category("phylum", IndexType::Taxon).unwrap(), "phylum");
+        assert_eq!(
+            parse_category("assembly_level", IndexType::Taxon).unwrap(),
+            "assembly_level"
+        );
+        // not a keyword
+        assert!(parse_category("genome_size", IndexType::Taxon).is_err());
+        assert!(parse_category("not_a_category", IndexType::Taxon).is_err());
+    }
+
+    #[test]
+    fn test_per_taxon_urls() {
+        let mut r = base_report(ReportType::Sources);
+        r.search = vec!["Aves".into(), "Mammalia".into()];
+        r.per_taxon = true;
+        let urls = r.make_urls(vec!["a".into(), "b".into()]).unwrap();
+        assert_eq!(urls.len(), 2);
+        assert!(urls[0].contains("tax_tree%28Aves%29") && urls[0].contains("goat_cli_a"));
+        assert!(urls[1].contains("tax_tree%28Mammalia%29") && urls[1].contains("goat_cli_b"));
+
+        r.per_taxon = false;
+        let urls = r.make_urls(vec!["a".into(), "b".into()]).unwrap();
+        assert_eq!(urls.len(), 1);
+        assert!(urls[0].contains("tax_tree%28Aves%2CMammalia%29"));
+    }
+
+    #[test]
+    fn test_arc_missing_x_returns_err() {
         let r = base_report(ReportType::Arc);
         let result = r.make_url(vec!["test_id".into()]);
         assert!(result.is_err());
-        assert!(result
-            .unwrap_err()
-            .to_string()
-            .contains("not yet implemented"));
+        assert!(result.unwrap_err().to_string().contains("x expression"));
+    }
+
+    #[test]
+    fn test_arc_url_defaults_y_to_taxa() {
+        let mut r = base_report(ReportType::Arc);
+        r.search = vec!["Lepidoptera".into()];
+        r.rank = "family".into();
+        r.x = Some(format_expression("assembly_level = chromosome", IndexType::Taxon).unwrap());
+        let url = r.make_url(vec!["id1".into()]).unwrap();
+        assert!(url.contains("report=arc"));
+        assert!(url.contains("rank=family"));
+        assert!(url.contains("x=tax_tree%28Lepidoptera%29+AND+assembly_level"));
+        assert!(url.contains("y=tax_tree%28Lepidoptera%29&"));
     }
 
     // ── Newick URL ───────────────────────────────────────────────────────────
@@ -553,6 +938,7 @@ mod tests {
             rank: "species".into(),
             taxon_type: TaxType::Tree,
             threshold: 2000,
+            goat_url: GOAT_URL.clone(),
             ..Default::default()
         };
         let url = r.make_url(vec!["test123".into()]).unwrap();
@@ -571,6 +957,7 @@ mod tests {
             rank: "species".into(),
             taxon_type: TaxType::Tree,
             threshold: 2000,
+            goat_url: GOAT_URL.clone(),
             ..Default::default()
         };
         let url = r.make_url(vec!["id1".into()]).unwrap();
@@ -587,6 +974,78 @@ mod tests {
         assert!(url.contains("treeThreshold=500"));
     }
 
+    #[test]
+    fn test_newick_url_uses_configured_goat_url() {
+        let mut r = base_report(ReportType::Newick);
+        r.goat_url = "http://localhost:3000/api/v2/".into();
+        let url = r.make_url(vec!["id1".into()]).unwrap();
+        assert!(url.starts_with("http://localhost:3000/api/v2/report?"));
+    }
+
+    #[test]
+    fn test_files_url() {
+        let mut r = base_report(ReportType::Files);
+        r.x = Some(format_expression("assembly_level = chromosome", IndexType::Taxon).unwrap());
+        let url = r.make_url(vec!["id1".into()]).unwrap();
+        assert!(url.contains("report=files"));
+        assert!(url.contains("x=tax_tree%28Homo+sapiens%29+AND+assembly_level"));
+        assert!(url.contains("queryId=goat_cli_id1"));
+    }
+
+    #[test]
+    fn test_oxford_url() {
+        let mut r = base_report(ReportType::Oxford);
+        r.index_type = IndexType::Assembly;
+        r.search = vec!["GCA_905220365.1".into(), "GCA_905147765.1".into()];
+        let url = r.make_url(vec!["id1".into()]).unwrap();
+        assert!(url.contains("report=oxford"));
+        assert!(url.contains("result=feature"));
+        assert!(url.contains(
+            "x=assembly_id%3DGCA_905220365.1%2CGCA_905147765.1+AND+feature_type%3Dbusco-gene"
+        ));
+        assert!(url.contains("cat=assembly_id"));
+
+        r.search.pop();
+        assert!(r.make_url(vec!["id1".into()]).is_err());
+    }
+
+    #[test]
+    fn test_newick_urls_batch_large_lists() {
+        let mut r = base_report(ReportType::Newick);
+        r.search = (0..1200).map(|i| format!("taxon{}", i)).collect();
+        let ids: Vec<String> = vec!["a".into(), "b".into(), "c".into()];
+        let urls = r.make_urls(ids.clone()).unwrap();
+        assert_eq!(urls.len(), 3);
+        assert!(urls[0].contains("taxon499%29"));
+        assert!(urls[1].contains("tax_tree%28taxon500%2C"));
+        assert!(urls[2].contains("queryId=goat_cli_c"));
+        assert_eq!(r.search_urls(&ids).len(), 3);
+
+        let url = r.ancestors_url(&["Hominidae".into(), "Pan".into()], "d");
+        assert!(url.contains("x=tax_name%28Hominidae%2CPan%29&"));
+        assert!(url.contains("queryId=goat_cli_d"));
+    }
+
+    #[test]
+    fn test_newick_annotation_search_url() {
+        let mut r = base_report(ReportType::Newick);
+        r.threshold = 500;
+        r.variables = vec!["genome_size".into(), "c_value".into()];
+        let url = r.search_urls(&["id1".into()]).remove(0);
+        assert!(url.contains("/search?"));
+        assert!(url.contains("fields=genome_size%2Cc_value"));
+        assert!(url.contains("size=500"));
+        assert!(url.contains("tax_rank%28species%29"));
+    }
+
+    #[test]
+    fn test_newick_url_assembly_result() {
+        let mut r = base_report(ReportType::Newick);
+        r.index_type = IndexType::Assembly;
+        let url = r.make_url(vec!["id1".into()]).unwrap();
+        assert!(url.contains("result=assembly"));
+    }
+
     // ── Histogram URL success ────────────────────────────────────────────────
 
     #[test]
@@ -603,6 +1062,80 @@ mod tests {
         assert!(url.contains("result=taxon"));
     }
 
+    // ── Scatter URL ──────────────────────────────────────────────────────────
+
+    #[test]
+    fn test_scatter_missing_y_returns_err() {
+        let mut r = base_report(ReportType::Scatterplot);
+        r.x = Some("genome_size".into());
+        let result = r.make_url(vec!["id1".into()]);
+        assert!(result.is_err());
+        assert!(result.unwrap_err().to_string().contains("y variable"));
+    }
+
+    #[test]
+    fn test_scatter_url_contains_key_parts() {
+        let mut r = base_report(ReportType::Scatterplot);
+        r.x = Some("genome_size".into());
+        r.y = Some("c_value".into());
+        r.threshold = 10000;
+        r.category = Some("assembly_level".into());
+        r.size = Some(5);
+        r.y_opts = Some(Opts::try_from_string(",,20").unwrap());
+        let url = r.make_url(vec!["id1".into()]).unwrap();
+        assert!(url.contains("report=scatter"));
+        assert!(url.contains("y=c_value"));
+        assert!(url.contains("rank=species"));
+        assert!(url.contains("scatterThreshold=10000"));
+        assert!(url.contains("cat=assembly_level%5B5%5D"));
+        assert!(url.contains("yOpts=%2C%2C20"));
+        assert!(!url.contains("xOpts"));
+    }
+
+    // ── Map URL ──────────────────────────────────────────────────────────────
+
+    #[test]
+    fn test_map_url_contains_key_parts() {
+        let mut r = base_report(ReportType::Map);
+        r.search = vec!["Aves".into()];
+        r.x = Some(format_expression("genome_size > 1000", IndexType::Taxon).unwrap());
+        r.category = Some("assembly_level".into());
+        r.size = Some(3);
+        let url = r.make_url(vec!["id1".into()]).unwrap();
+        assert!(url.contains("report=map"));
+        assert!(url.contains("x=tax_tree%28Aves%29+AND+genome_size"));
+        assert!(url.contains("regionField=country_list"));
+        assert!(url.contains("cat=assembly_level%5B3%5D"));
+    }
+
+    // ── xPerRank URL ─────────────────────────────────────────────────────────
+
+    #[test]
+    fn test_per_rank_url_contains_ranks() {
+        let mut r = base_report(ReportType::PerRank);
+        r.search = vec!["Lepidoptera".into()];
+        r.ranks = vec!["genus".into(), "family".into()];
+        let url = r.make_url(vec!["id1".into()]).unwrap();
+        assert!(url.contains("report=xPerRank"));
+        assert!(url.contains("x=tax_tree%28Lepidoptera%29&"));
+        assert!(url.contains("ranks=genus%2Cfamily"));
+        assert!(!url.contains("rank="));
+    }
+
+    // ── Sources URL ──────────────────────────────────────────────────────────
+
+    #[test]
+    fn test_sources_url_contains_taxa_and_rank() {
+        let mut r = base_report(ReportType::Sources);
+        r.search = vec!["Orobanchaceae".into(), "Aves".into()];
+        r.rank = "genus".into();
+        let url = r.make_url(vec!["id1".into()]).unwrap();
+        assert!(url.contains("report=sources"));
+        assert!(url.contains("query=tax_tree%28Orobanchaceae%2CAves%29+AND+tax_rank%28genus%29"));
+        assert!(url.contains("summaryValues=count"));
+        assert!(url.contains("queryId=goat_cli_id1"));
+    }
+
     // ── Opts::try_from_string ────────────────────────────────────────────────
 
     #[test]
@@ -0,0 +1,434 @@
+use crate::error::{Error, ErrorKind, Result};
+use crate::report::plot::{bound, histogram_bins};
+use crate::report::report::Opts;
+use crate::report::models::{cell, report_body};
+use serde_json::Value;
+
+/// The size of the drawing, in pixels.
+const WIDTH: f64 = 640.0;
+const HEIGHT: f64 = 400.0;
+/// Space around the plot area for the axes and their titles.
+const MARGIN_LEFT: f64 = 80.0;
+const MARGIN_RIGHT: f64 = 20.0;
+const MARGIN_TOP: f64 = 30.0;
+const MARGIN_BOTTOM: f64 = 60.0;
+/// The default number of ticks on an axis.
+const DEFAULT_TICKS: usize = 5;
+/// The colours for each category, also used for iTOL datasets.
+pub(crate) const PALETTE: [&str; 8] = [
+    "#1f77b4", "#ff7f0e", "#2ca02c", "#d62728", "#9467bd", "#8c564b", "#e377c2", "#7f7f7f",
+];
+
+/// Escape text for use in SVG, or any other XML.
+pub(crate) fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// An axis of the plot, mapping values to pixels.
+struct Axis {
+    /// The scale, one of [`Opts::SCALE_TYPES`]. Ordinal axes
+    /// place the buckets evenly by their index.
+    scale: String,
+    /// The domain, after scaling.
+    lo: f64,
+    hi: f64,
+    /// The pixel range.
+    start: f64,
+    end: f64,
+    ticks: usize,
+    title: String,
+}
+
+impl Axis {
+    /// Make an axis over `buckets`, using the scale, limits,
+    /// tick count and title from `opts`, if given.
+    fn new(buckets: &[Value], opts: Option<&Opts>, title: &str, range: (f64, f64)) -> Self {
+        let numeric: Option<Vec<f64>> = buckets.iter().map(|b| b.as_f64()).collect();
+        let scale = match (opts.and_then(|o| o.scale.clone()), &numeric) {
+            (Some(s), Some(_)) => s,
+            (Some(_), None) | (None, None) => "ordinal".into(),
+            (None, Some(_)) => "linear".into(),
+        };
+
+        let mut axis = Axis {
+            scale,
+            lo: 0.0,
+            hi: buckets.len().max(1) as f64,
+            start: range.0,
+            end: range.1,
+            ticks: opts
+                .and_then(|o| o.tick_count)
+                .map_or(DEFAULT_TICKS, |t| t.max(2) as usize),
+            title: opts
+                .and_then(|o| o.axis_title.clone())
+                .unwrap_or_else(|| title.to_string()),
+        };
+
+        if let (Some(values), false) = (numeric, axis.scale == "ordinal") {
+            let first = values.first().copied().unwrap_or(0.0);
+            let last = values.last().copied().unwrap_or(1.0);
+            let min = opts.and_then(|o| o.min).map_or(first, f64::from);
+            let max = opts.and_then(|o| o.max).map_or(last, f64::from);
+            axis.lo = axis.forward(min);
+            axis.hi = axis.forward(max);
+            if axis.hi <= axis.lo {
+                axis.hi = axis.lo + 1.0;
+            }
+        }
+        axis
+    }
+
+    /// Apply the scale to a value.
+    fn forward(&self, value: f64) -> f64 {
+        // log scales can't show zero, so clamp to a small value
+        let positive = value.max(f64::MIN_POSITIVE);
+        match self.scale.as_str() {
+            "log10" => positive.log10(),
+            "log2" => positive.log2(),
+            "log" => positive.ln(),
+            "sqrt" => value.max(0.0).sqrt(),
+            _ => value,
+        }
+    }
+
+    /// Undo the scale, for labelling ticks.
+    fn inverse(&self, value: f64) -> f64 {
+        match self.scale.as_str() {
+            "log10" => 10f64.powf(value),
+            "log2" => 2f64.powf(value),
+            "log" => value.exp(),
+            "sqrt" => value * value,
+            _ => value,
+        }
+    }
+
+    /// The pixel position of a bucket bound, which is its index
+    /// on an ordinal axis.
+    fn position(&self, bucket: &Value, index: usize) -> f64 {
+        let value = match (self.scale.as_str(), bucket.as_f64()) {
+            ("ordinal", _) | (_, None) => index as f64,
+            (_, Some(v)) => self.forward(v),
+        };
+        let fraction = ((value - self.lo) / (self.hi - self.lo)).clamp(0.0, 1.0);
+        self.start + fraction * (self.end - self.start)
+    }
+
+    /// The tick positions and labels. Ordinal axes label each
+    /// bucket.
+    fn tick_marks(&self, buckets: &[Value]) -> Vec<(f64, String)> {
+        match self.scale.as_str() {
+            "ordinal" => buckets
+                .iter()
+                .enumerate()
+                .map(|(i, b)| (self.position(&Value::Null, i), bound(b)))
+                .collect(),
+            _ => (0..self.ticks)
+                .map(|i| {
+                    let scaled = self.lo + (self.hi - self.lo) * i as f64 / (self.ticks - 1) as f64;
+                    let fraction = (scaled - self.lo) / (self.hi - self.lo);
+                    let label = bound(&serde_json::json!(self.inverse(scaled)));
+                    (self.start + fraction * (self.end - self.start), label)
+                })
+                .collect(),
+        }
+    }
+
+    /// Draw the axis along the bottom (`horizontal`) or the left
+    /// of the plot area.
+    fn draw(&self, buckets: &[Value], horizontal: bool) -> String {
+        let mut svg = String::new();
+        let bottom = HEIGHT - MARGIN_BOTTOM;
+        match horizontal {
+            true => {
+                svg += &format!(
+                    r#"<line x1="{}" y1="{bottom}" x2="{}" y2="{bottom}" stroke="black"/>"#,
+                    MARGIN_LEFT,
+                    WIDTH - MARGIN_RIGHT
+                );
+                for (x, label) in self.tick_marks(buckets) {
+                    svg += &format!(
+                        r#"<line x1="{x:.1}" y1="{bottom}" x2="{x:.1}" y2="{}" stroke="black"/><text x="{x:.1}" y="{}" text-anchor="middle">{}</text>"#,
+                        bottom + 5.0,
+                        bottom + 18.0,
+                        escape(&label)
+                    );
+                }
+                svg += &format!(
+                    r#"<text x="{}" y="{}" text-anchor="middle">{}</text>"#,
+                    MARGIN_LEFT + (WIDTH - MARGIN_LEFT - MARGIN_RIGHT) / 2.0,
+                    HEIGHT - 15.0,
+                    escape(&self.title)
+                );
+            }
+            false => {
+                svg += &format!(
+                    r#"<line x1="{MARGIN_LEFT}" y1="{MARGIN_TOP}" x2="{MARGIN_LEFT}" y2="{bottom}" stroke="black"/>"#
+                );
+                for (y, label) in self.tick_marks(buckets) {
+                    svg += &format!(
+                        r#"<line x1="{}" y1="{y:.1}" x2="{MARGIN_LEFT}" y2="{y:.1}" stroke="black"/><text x="{}" y="{:.1}" text-anchor="end">{}</text>"#,
+                        MARGIN_LEFT - 5.0,
+                        MARGIN_LEFT - 8.0,
+                        y + 4.0,
+                        escape(&label)
+                    );
+                }
+                let middle = MARGIN_TOP + (bottom - MARGIN_TOP) / 2.0;
+                svg += &format!(
+                    r#"<text x="15" y="{middle}" text-anchor="middle" transform="rotate(-90 15 {middle})">{}</text>"#,
+                    escape(&self.title)
+                );
+            }
+        }
+        svg
+    }
+}
+
+/// The upper bound of bin `i`. If the API doesn't return the
+/// upper bound of the last bin, it is as wide as the bin before.
+fn upper(buckets: &[Value], i: usize) -> Value {
+    match buckets.get(i + 1) {
+        Some(b) => b.clone(),
+        None => match (
+            buckets.get(i).and_then(|b| b.as_f64()),
+            i.checked_sub(1).and_then(|p| buckets.get(p)?.as_f64()),
+        ) {
+            (Some(last), Some(previous)) => serde_json::json!(last + (last - previous)),
+            _ => Value::Null,
+        },
+    }
+}
+
+/// Wrap the drawing in an SVG document, with a legend if
+/// there are categories.
+fn document(body: String, labels: &[String]) -> String {
+    let mut svg = format!(
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{WIDTH}" height="{HEIGHT}" viewBox="0 0 {WIDTH} {HEIGHT}" font-family="sans-serif" font-size="12">"#
+    );
+    svg += "\n";
+    svg += &format!(r#"<rect width="{WIDTH}" height="{HEIGHT}" fill="white"/>"#);
+    svg += "\n";
+    svg += &body;
+    for (i, label) in labels.iter().enumerate() {
+        let y = MARGIN_TOP + 16.0 * i as f64;
+        svg += &format!(
+            r#"<rect x="{}" y="{}" width="10" height="10" fill="{}"/><text x="{}" y="{}">{}</text>"#,
+            WIDTH - MARGIN_RIGHT - 120.0,
+            y - 9.0,
+            PALETTE[i % PALETTE.len()],
+            WIDTH - MARGIN_RIGHT - 105.0,
+            y,
+            escape(label)
+        );
+        svg += "\n";
+    }
+    svg += "</svg>\n";
+    svg
+}
+
+/// Draw a histogram report as an SVG bar chart, stacked by
+/// category if there are any. The x axis follows `x_opts`.
+pub fn histogram(
+    response: &Value,
+    report_name: &str,
+    x_opts: Option<&Opts>,
+    x_title: &str,
+) -> Result<String> {
+    let bins = histogram_bins(response, report_name)?;
+    let bottom = HEIGHT - MARGIN_BOTTOM;
+    let x_axis = Axis::new(
+        &bins.buckets,
+        x_opts,
+        x_title,
+        (MARGIN_LEFT, WIDTH - MARGIN_RIGHT),
+    );
+    let max = bins.max().max(1);
+    let y_buckets: Vec<Value> = vec![serde_json::json!(0), serde_json::json!(max)];
+    let y_axis = Axis::new(&y_buckets, None, "Count", (bottom, MARGIN_TOP));
+
+    let mut body = String::new();
+    for i in 0..bins.len() {
+        let x0 = x_axis.position(bins.buckets.get(i).unwrap_or(&Value::Null), i);
+        let x1 = x_axis.position(&upper(&bins.buckets, i), i + 1);
+        let mut total = 0;
+        for (c, count) in bins.bin(i).iter().enumerate() {
+            if *count == 0 {
+                continue;
+            }
+            let y0 = y_axis.position(&serde_json::json!(total), 0);
+            total += count;
+            let y1 = y_axis.position(&serde_json::json!(total), 0);
+            body += &format!(
+                r#"<rect x="{:.1}" y="{:.1}" width="{:.1}" height="{:.1}" fill="{}"><title>{}</title></rect>"#,
+                x0,
+                y1,
+                (x1 - x0 - 1.0).max(1.0),
+                y0 - y1,
+                PALETTE[c % PALETTE.len()],
+                count
+            );
+            body += "\n";
+        }
+    }
+    body += &x_axis.draw(&bins.buckets, true);
+    body += "\n";
+    body += &y_axis.draw(&y_buckets, false);
+    body += "\n";
+
+    Ok(document(body, &bins.labels))
+}
+
+/// Draw a scatter report as an SVG heatmap of the binned counts,
+/// with darker cells for higher counts. The axes follow `x_opts`
+/// and `y_opts`.
+pub fn scatter(
+    response: &Value,
+    x_opts: Option<&Opts>,
+    y_opts: Option<&Opts>,
+    titles: (&str, &str),
+) -> Result<String> {
+    let scatter = report_body(response, "scatter")?;
+    let histograms = &scatter["histograms"];
+    let (x_buckets, y_buckets) = match (
+        histograms["buckets"].as_array(),
+        histograms["yBuckets"].as_array(),
+    ) {
+        (Some(x), Some(y)) => (x, y),
+        _ => {
+            return Err(Error::new(ErrorKind::Report(
+                "no data found for the scatter report.".into(),
+            )))
+        }
+    };
+
+    // one table of counts per category, indexed by [x bin][y bin]
+    let cats = scatter["cats"].as_array().filter(|c| !c.is_empty());
+    let (labels, tables): (Vec<String>, Vec<&Value>) = match cats {
+        Some(cats) => cats
+            .iter()
+            .map(|cat| {
+                let key = cell(&cat["key"]);
+                let table = &histograms["byCat"][&key];
+                (cat["label"].as_str().map_or(key, String::from), table)
+            })
+            .unzip(),
+        None => (vec![], vec![&histograms["allValues"]]),
+    };
+    let count = |table: &Value, i: usize, j: usize| table[i][j].as_u64().unwrap_or(0);
+    let max = tables
+        .iter()
+        .flat_map(|t| t.as_array().into_iter().flatten())
+        .flat_map(|row| row.as_array().into_iter().flatten())
+        .filter_map(|c| c.as_u64())
+        .max()
+        .unwrap_or(0)
+        .max(1);
+
+    let bottom = HEIGHT - MARGIN_BOTTOM;
+    let x_axis = Axis::new(
+        x_buckets,
+        x_opts,
+        titles.0,
+        (MARGIN_LEFT, WIDTH - MARGIN_RIGHT),
+    );
+    let y_axis = Axis::new(y_buckets, y_opts, titles.1, (bottom, MARGIN_TOP));
+
+    let mut body = String::new();
+    for (c, table) in tables.iter().enumerate() {
+        let n_x = table.as_array().map_or(0, |t| t.len());
+        for i in 0..n_x {
+            let n_y = table[i].as_array().map_or(0, |t| t.len());
+            let x0 = x_axis.position(x_buckets.get(i).unwrap_or(&Value::Null), i);
+            let x1 = x_axis.position(&upper(x_buckets, i), i + 1);
+            for j in 0..n_y {
+                let n = count(table, i, j);
+                if n == 0 {
+                    continue;
+                }
+                let y0 = y_axis.position(y_buckets.get(j).unwrap_or(&Value::Null), j);
+                let y1 = y_axis.position(&upper(y_buckets, j), j + 1);
+                body += &format!(
+                    r#"<rect x="{:.1}" y="{:.1}" width="{:.1}" height="{:.1}" fill="{}" fill-opacity="{:.2}"><title>{}</title></rect>"#,
+                    x0,
+                    y1,
+                    (x1 - x0).max(1.0),
+                    (y0 - y1).max(1.0),
+                    PALETTE[c % PALETTE.len()],
+                    0.15 + 0.85 * n as f64 / max as f64,
+                    n
+                );
+                body += "\n";
+            }
+        }
+    }
+    body += &x_axis.draw(x_buckets, true);
+    body += "\n";
+    body += &y_axis.draw(y_buckets, false);
+    body += "\n";
+
+    Ok(document(body, &labels))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_axis_log10_scale() {
+        let buckets = vec![json!(1000), json!(10000), json!(100000)];
+        let opts = Opts::try_from_string(",,3,log10,Genome size").unwrap();
+        let axis = Axis::new(&buckets, Some(&opts), "genome_size", (0.0, 100.0));
+        assert_eq!(axis.title, "Genome size");
+        assert!((axis.position(&json!(10000), 1) - 50.0).abs() < 1e-9);
+        let ticks = axis.tick_marks(&buckets);
+        assert_eq!(
+            ticks.iter().map(|t| t.1.as_str()).collect::<Vec<_>>(),
+            vec!["1k", "10k", "100k"]
+        );
+    }
+
+    #[test]
+    fn test_axis_min_max_from_opts() {
+        let buckets = vec![json!(0), json!(50), json!(100)];
+        let opts = Opts::try_from_string("0,200").unwrap();
+        let axis = Axis::new(&buckets, Some(&opts), "x", (0.0, 100.0));
+        assert!((axis.position(&json!(100), 2) - 50.0).abs() < 1e-9);
+        assert_eq!(axis.title, "x");
+    }
+
+    #[test]
+    fn test_histogram_svg() {
+        let response = json!({"report": {"table": {
+            "cats": [{"key": "chromosome"}, {"key": "a<b"}],
+            "histograms": {
+                "buckets": [0, 10, 20],
+                "byCat": {"chromosome": [1, 0], "a<b": [3, 2]}
+            }
+        }}});
+        let svg = histogram(&response, "table", None, "genome_size").unwrap();
+        assert!(svg.starts_with("<svg"));
+        assert!(svg.trim_end().ends_with("</svg>"));
+        // one rect per non-zero count, plus the background and legend
+        assert_eq!(svg.matches("<title>").count(), 3);
+        assert!(svg.contains("a&lt;b"));
+        assert!(svg.contains(">genome_size</text>"));
+    }
+
+    #[test]
+    fn test_scatter_svg() {
+        let response = json!({"report": {"scatter": {
+            "histograms": {
+                "buckets": [1, 10, 100],
+                "yBuckets": [0, 1],
+                "allValues": [[3, 0], [5, 6]]
+            }
+        }}});
+        let svg = scatter(&response, None, None, ("x", "y")).unwrap();
+        assert_eq!(svg.matches("<title>").count(), 3);
+        assert!(svg.contains(r#"fill-opacity="1.00""#));
+    }
+}
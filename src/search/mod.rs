@@ -3,41 +3,142 @@
 //! `goat-cli search <args>`
 
 use futures::StreamExt;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::io::BufWriter;
+use std::path::PathBuf;
 
 use crate::client::GoatClient;
-use crate::error::Result;
+use crate::error::{Error, ErrorKind, Result};
 use crate::utils::cli_matches::CliAction;
-use crate::utils::{cli_matches, utils};
-use crate::{count, IndexType};
+use crate::utils::columnar::{self, ParquetWriter};
+use crate::utils::output::{search_records, unique_records, JsonWriter, OutputFormat, TsvWriter};
+use crate::utils::sqlite::SqliteWriter;
+use crate::utils::{cli_matches, url};
+use crate::{count, IndexType, UPPER_CLI_SIZE_LIMIT};
 
-/// Execute the `search` subcommand from `goat-cli`. Print a TSV.
+/// Execute the `search` subcommand from `goat-cli`. Print a TSV (or
+/// the `--output-format` chosen), streaming rows to stdout as each
+/// response arrives.
+///
+/// With `--sqlite`, results are written to a local database
+/// instead (see [`SqliteWriter`]).
+///
+/// Searches returning more than [`UPPER_CLI_SIZE_LIMIT`] results
+/// are paged through the API.
 pub async fn search(
     matches: &clap::ArgMatches,
     unique_ids: Vec<String>,
     index_type: IndexType,
 ) -> Result<()> {
-    let url_vector_api =
+    let (size, url_vector_api) =
         match cli_matches::process_cli_args(matches, "search", unique_ids.clone(), index_type)? {
-            CliAction::Continue { urls, .. } => urls,
+            CliAction::Continue { size, urls, .. } => (size, urls),
             CliAction::PrintedAndExit => return Ok(()),
         };
 
     let concurrent_requests = url_vector_api.len();
 
-    // print count warnings.
-    count::count(matches, false, true, unique_ids, index_type).await?;
+    // print count warnings, and get the number of hits for each
+    // search so we know how many pages to request.
+    let (counts, branches) =
+        match count::count_per_query(matches, true, unique_ids, index_type).await? {
+            Some(c) => c,
+            None => return Ok(()),
+        };
+    // an expression using OR makes a query for each branch, so
+    // results for each taxon are unioned.
+    let union = branches > 1;
+    // each branch returns up to `size` results, so the union is
+    // capped at `size` too.
+    let mut seen = HashMap::new();
+
+    let page_size = *UPPER_CLI_SIZE_LIMIT as u64;
+    let url_pages = url_vector_api
+        .iter()
+        .zip(counts.iter())
+        .flat_map(|(path, (search_query, count))| {
+            url::paginate_url(path, size.min(*count), page_size)
+                .into_iter()
+                .map(move |page| (search_query.clone(), page))
+        })
+        .collect::<Vec<(String, String)>>();
 
+    let output_format = OutputFormat::from_matches(matches);
+    output_format.check_stdout()?;
+    let stdout = BufWriter::new(std::io::stdout());
     let client = GoatClient::new();
-    let fetches = futures::stream::iter(url_vector_api.into_iter().map(|path| {
-        let client = client.clone();
-        async move { client.get_text(&path, "text/tab-separated-values").await }
-    }))
-    .buffered(concurrent_requests)
-    .collect::<Vec<_>>();
+    // `--sqlite` writes to a database instead of stdout.
+    let sqlite = matches.get_one::<PathBuf>("sqlite");
+
+    // write each response as soon as it (and those before it) arrive.
+    match (sqlite, output_format) {
+        (None, OutputFormat::Tsv | OutputFormat::Csv) => {
+            let mut fetches =
+                futures::stream::iter(url_pages.into_iter().map(|(search_query, path)| {
+                    let client = client.clone();
+                    async move {
+                        let response = client.get_text(&path, output_format.accept_header()).await;
+                        (search_query, response)
+                    }
+                }))
+                .buffered(concurrent_requests);
+
+            let mut writer = match union {
+                true => TsvWriter::new(stdout).unique_rows(size as usize),
+                false => TsvWriter::new(stdout),
+            };
+            while let Some((search_query, response)) = fetches.next().await {
+                let tsv = response.map_err(|e| Error::new(ErrorKind::FormatTSV(e.to_string())))?;
+                writer.write_response(&search_query, &tsv)?;
+            }
+            writer.finish()?;
+        }
+        _ => {
+            // the columns are the same for every search, so type them
+            // from the first.
+            let columns = match url_pages.first() {
+                Some((_, path)) => columnar::search_columns(path, index_type)?,
+                None => vec![],
+            };
+
+            let mut unique = |records: Vec<Value>| match union {
+                true => unique_records(records, &mut seen, size as usize),
+                false => records,
+            };
 
-    let awaited_fetches = fetches.await;
+            let mut fetches =
+                futures::stream::iter(url_pages.into_iter().map(|(search_query, path)| {
+                    let client = client.clone();
+                    async move {
+                        let v = client.get_json(&path).await?;
+                        search_records(&v, &search_query)
+                    }
+                }))
+                .buffered(concurrent_requests);
 
-    utils::format_tsv_output(awaited_fetches)?;
+            if let Some(path) = sqlite {
+                let mut writer = SqliteWriter::new(path, index_type, columns)?;
+                while let Some(records) = fetches.next().await {
+                    writer.write_records(&unique(records?))?;
+                }
+                let rows = writer.finish()?;
+                eprintln!("Wrote {} rows to {}", rows, path.display());
+            } else if output_format == OutputFormat::Parquet {
+                let mut writer = ParquetWriter::new(stdout, columns)?;
+                while let Some(records) = fetches.next().await {
+                    writer.write_records(&unique(records?))?;
+                }
+                writer.finish()?;
+            } else {
+                let mut writer = JsonWriter::new(stdout, output_format);
+                while let Some(records) = fetches.next().await {
+                    writer.write_records(&unique(records?))?;
+                }
+                writer.finish()?;
+            }
+        }
+    }
 
     Ok(())
 }
@@ -0,0 +1,291 @@
+use crate::client::GoatClient;
+use crate::utils::expression::{Function, TypeOf, Variable};
+use crate::utils::variable_data::{GOAT_ASSEMBLY_VARIABLE_DATA, GOAT_TAXON_VARIABLE_DATA};
+use crate::{IndexType, TAXONOMY};
+
+use clap::parser::ValueSource;
+use lazy_static::lazy_static;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+use std::time::{Duration, SystemTime};
+
+lazy_static! {
+    /// How long a cached copy of the variables is used before
+    /// fetching them again. Override (in seconds) with the
+    /// `GOAT_CLI_CACHE_TTL` environment variable.
+    pub static ref VARIABLE_CACHE_TTL: Duration = std::env::var("GOAT_CLI_CACHE_TTL")
+        .ok()
+        .and_then(|e| e.parse().ok())
+        .map(Duration::from_secs)
+        .unwrap_or(Duration::from_secs(60 * 60 * 24));
+    /// How long to wait for the API before falling back to
+    /// the cached, or compiled-in, variables.
+    static ref FETCH_TIMEOUT: Duration = Duration::from_secs(5);
+}
+
+/// The variables fetched at runtime for each index.
+static TAXON_CATALOGUE: OnceLock<BTreeMap<&'static str, Variable<'static>>> = OnceLock::new();
+static ASSEMBLY_CATALOGUE: OnceLock<BTreeMap<&'static str, Variable<'static>>> = OnceLock::new();
+
+/// The variable data for an index. This is the catalogue loaded
+/// by [`load`] if there is one, otherwise the compiled-in
+/// [`GOAT_TAXON_VARIABLE_DATA`] or [`GOAT_ASSEMBLY_VARIABLE_DATA`].
+pub fn variable_data(index_type: IndexType) -> &'static BTreeMap<&'static str, Variable<'static>> {
+    match index_type {
+        IndexType::Taxon => TAXON_CATALOGUE.get().unwrap_or(&GOAT_TAXON_VARIABLE_DATA),
+        IndexType::Assembly => ASSEMBLY_CATALOGUE
+            .get()
+            .unwrap_or(&GOAT_ASSEMBLY_VARIABLE_DATA),
+    }
+}
+
+/// Load the variables for an index from the `resultFields` endpoint
+/// of the API, so variables added to GoaT since this release can be
+/// used straight away.
+///
+/// The response is cached on disk for [`VARIABLE_CACHE_TTL`]. If the
+/// API can't be reached, a stale cache is used, and failing that
+/// the compiled-in variables are kept.
+pub async fn load(goat_url: &str, index_type: IndexType) {
+    let cell = match index_type {
+        IndexType::Taxon => &TAXON_CATALOGUE,
+        IndexType::Assembly => &ASSEMBLY_CATALOGUE,
+    };
+    if cell.get().is_some() {
+        return;
+    }
+
+    let path = cache_path(goat_url, index_type);
+    let cached = path.as_ref().and_then(read_cache);
+
+    let result_fields = match cached {
+        Some((fields, age)) if age < *VARIABLE_CACHE_TTL => Some(fields),
+        cached => match fetch(goat_url, index_type).await {
+            Some(fields) => {
+                if let Some(p) = &path {
+                    write_cache(p, &fields);
+                }
+                Some(fields)
+            }
+            None => cached.map(|(fields, _)| fields),
+        },
+    };
+
+    if let Some(fields) = result_fields {
+        let data = parse_result_fields(&fields);
+        if !data.is_empty() {
+            let _ = cell.set(data);
+        }
+    }
+}
+
+/// The arguments whose values are checked against the variables,
+/// e.g. `-v`, `-e`, `--x-variable` and `--category`.
+const VARIABLE_ARGS: [&str; 8] = [
+    "variables",
+    "expression",
+    "print-expression",
+    "x-variable",
+    "y-variable",
+    "x-expression",
+    "y-expression",
+    "category",
+];
+
+/// [`load`] the variables for an index, but only if the command
+/// was given one of the [`VARIABLE_ARGS`]. Other commands (e.g.
+/// `lookup`, or a search by taxon alone) don't wait on the API.
+pub async fn load_for(matches: &clap::ArgMatches, goat_url: &str, index_type: IndexType) {
+    if checks_variables(matches) {
+        load(goat_url, index_type).await;
+    }
+}
+
+/// Whether any of the [`VARIABLE_ARGS`] were given. Not every
+/// command has all of them.
+fn checks_variables(matches: &clap::ArgMatches) -> bool {
+    VARIABLE_ARGS.iter().any(|id| {
+        matches.try_contains_id(id).unwrap_or(false)
+            && matches.value_source(id) == Some(ValueSource::CommandLine)
+    })
+}
+
+/// Fetch `resultFields` for an index, giving up after [`FETCH_TIMEOUT`].
+async fn fetch(goat_url: &str, index_type: IndexType) -> Option<Value> {
+    let url = format!(
+        "{}resultFields?result={}&taxonomy={}",
+        goat_url, index_type, *TAXONOMY
+    );
+    let client = GoatClient::new();
+    match tokio::time::timeout(*FETCH_TIMEOUT, client.get_json(&url)).await {
+        Ok(Ok(fields)) if fields["fields"].is_object() => Some(fields),
+        _ => None,
+    }
+}
+
+/// The cache file for an index of a GoaT instance, in the user's
+/// cache directory (e.g. `~/.cache/goat-cli/`).
+fn cache_path(goat_url: &str, index_type: IndexType) -> Option<PathBuf> {
+    let cache_dir = std::env::var_os("XDG_CACHE_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|h| PathBuf::from(h).join(".cache")))
+        .or_else(|| std::env::var_os("LOCALAPPDATA").map(PathBuf::from))?;
+    // keep instances apart, e.g. a private genomehubs instance
+    let instance = goat_url
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect::<String>();
+    Some(
+        cache_dir
+            .join("goat-cli")
+            .join(format!("{}{}_fields.json", instance, index_type)),
+    )
+}
+
+/// Read a cached `resultFields` response, and how old it is.
+fn read_cache(path: &PathBuf) -> Option<(Value, Duration)> {
+    let age = std::fs::metadata(path)
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|m| SystemTime::now().duration_since(m).ok())?;
+    let fields = serde_json::from_str(&std::fs::read_to_string(path).ok()?).ok()?;
+    Some((fields, age))
+}
+
+/// Cache a `resultFields` response. Failing to cache isn't an error,
+/// the variables will just be fetched again next time.
+fn write_cache(path: &PathBuf, fields: &Value) {
+    if let Some(parent) = path.parent() {
+        let _ = std::fs::create_dir_all(parent);
+    }
+    let _ = std::fs::write(path, fields.to_string());
+}
+
+/// Keep a string for the rest of the program, as the variable
+/// data is borrowed for `'static` everywhere it is used.
+fn leak(s: &str) -> &'static str {
+    Box::leak(s.to_string().into_boxed_str())
+}
+
+/// Parse a `resultFields` response into variable data, in the same
+/// way `vars/vars_to_string.py` makes the compiled-in tables.
+pub fn parse_result_fields(result_fields: &Value) -> BTreeMap<&'static str, Variable<'static>> {
+    let mut data = BTreeMap::new();
+    let fields = match result_fields["fields"].as_object() {
+        Some(f) => f,
+        None => return data,
+    };
+
+    for (key, field) in fields {
+        let name = field["name"].as_str().unwrap_or(key);
+        let display_name = field["display_name"].as_str().unwrap_or(name);
+
+        let type_of = match field["type"].as_str() {
+            Some("long") => TypeOf::Long,
+            Some("short") => TypeOf::Short,
+            Some("1dp") => TypeOf::OneDP,
+            Some("2dp") => TypeOf::TwoDP,
+            Some("integer") => TypeOf::Integer,
+            Some("date") => TypeOf::Date,
+            Some("half_float") => TypeOf::HalfFloat,
+            Some("keyword") => {
+                let keywords: Vec<&'static str> = field["constraint"]["enum"]
+                    .as_array()
+                    .map(|e| e.iter().filter_map(|k| k.as_str()).map(leak).collect())
+                    .unwrap_or_default();
+                // an empty keyword list is displayed as no keywords
+                match keywords.is_empty() {
+                    true => TypeOf::Keyword(vec![""]),
+                    false => TypeOf::Keyword(keywords),
+                }
+            }
+            _ => TypeOf::None,
+        };
+
+        // only min/max are supported for now.
+        let summary = field["summary"].as_array();
+        let has = |f: &str| summary.is_some_and(|s| s.iter().any(|e| e == f));
+        let functions = match has("min") && has("max") {
+            true => Function::Some(vec!["min", "max"]),
+            false => Function::None,
+        };
+
+        data.insert(
+            leak(name),
+            Variable {
+                display_name: leak(display_name),
+                type_of,
+                functions,
+            },
+        );
+    }
+
+    data
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_result_fields_matches_compiled_in_data() {
+        let fields: Value =
+            serde_json::from_str(include_str!("../../vars/taxon_vars.json")).unwrap();
+        let data = parse_result_fields(&fields);
+
+        let c_value = &data["c_value"];
+        assert_eq!(c_value.display_name, "C value");
+        assert!(matches!(c_value.type_of, TypeOf::HalfFloat));
+        assert!(matches!(&c_value.functions, Function::Some(f) if f == &vec!["min", "max"]));
+
+        assert!(
+            matches!(&data["assembly_level"].type_of, TypeOf::Keyword(k) if k.contains(&"chromosome"))
+        );
+        for name in GOAT_TAXON_VARIABLE_DATA.keys() {
+            assert!(data.contains_key(name), "{} missing", name);
+        }
+    }
+
+    #[test]
+    fn test_parse_result_fields_empty_keywords() {
+        let fields = serde_json::json!({"fields": {
+            "sequencing_status_new": {"name": "sequencing_status_new", "type": "keyword"}
+        }});
+        let data = parse_result_fields(&fields);
+        let variable = &data["sequencing_status_new"];
+        assert_eq!(variable.display_name, "sequencing_status_new");
+        assert!(matches!(&variable.type_of, TypeOf::Keyword(k) if k == &vec![""]));
+    }
+
+    #[test]
+    fn test_checks_variables() {
+        let command = clap::Command::new("search")
+            .arg(clap::Arg::new("taxon").short('t'))
+            .arg(clap::Arg::new("variables").short('v'))
+            .arg(clap::Arg::new("expression").short('e'))
+            .arg(
+                clap::Arg::new("url")
+                    .short('u')
+                    .action(clap::ArgAction::SetTrue),
+            )
+            .arg(
+                clap::Arg::new("print-expression")
+                    .short('p')
+                    .action(clap::ArgAction::SetTrue),
+            );
+        let checks = |args: &[&str]| checks_variables(&command.clone().get_matches_from(args));
+
+        assert!(!checks(&["search", "-t", "Homo"]));
+        assert!(!checks(&["search", "-t", "Homo", "-u"]));
+        assert!(checks(&["search", "-t", "Homo", "-v", "c_value"]));
+        assert!(checks(&["search", "-t", "Homo", "-e", "c_value > 1"]));
+        assert!(checks(&["search", "-p"]));
+    }
+
+    #[test]
+    fn test_variable_data_falls_back_to_compiled_in() {
+        assert!(variable_data(IndexType::Assembly).contains_key("assembly_level"));
+    }
+}
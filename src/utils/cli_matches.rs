@@ -1,9 +1,6 @@
 use crate::error::{Error, ErrorKind, Result};
-use crate::utils::{
-    expression, tax_ranks, url, utils,
-    variable_data::{GOAT_ASSEMBLY_VARIABLE_DATA, GOAT_TAXON_VARIABLE_DATA},
-};
-use crate::{IndexType, TaxType, GOAT_URL, TAXONOMY, UPPER_CLI_FILE_LIMIT, UPPER_CLI_SIZE_LIMIT};
+use crate::utils::{catalogue, expression, tax_ranks, url, utils};
+use crate::{IndexType, TaxType, TAXONOMY, UPPER_CLI_FILE_LIMIT};
 use std::path::PathBuf;
 
 pub enum CliAction {
@@ -11,6 +8,10 @@ pub enum CliAction {
         size: u64,
         taxa: Vec<String>,
         urls: Vec<String>,
+        /// The number of queries made for each taxon. More than one
+        /// if the expression uses `OR`, in which case the results of
+        /// each taxon need to be unioned.
+        branches: usize,
     },
     PrintedAndExit,
 }
@@ -48,9 +49,9 @@ pub fn process_cli_args(
         .get_one::<bool>("include-estimates")
         .copied()
         .unwrap_or(false);
-    let expression = match matches.get_one::<String>("expression") {
-        Some(s) => url::format_expression(s, index_type)?,
-        None => "".to_string(),
+    let expressions = match matches.get_one::<String>("expression") {
+        Some(s) => url::format_expression_branches(s, index_type)?,
+        None => vec!["".to_string()],
     };
     // map needed to convert Option<String> -> Option<&str>
     let variable_string = matches.get_one::<String>("variables").map(|x| &**x);
@@ -72,6 +73,7 @@ pub fn process_cli_args(
     let tax_name_op = matches.get_one::<String>("taxon");
     let filename_op = matches.get_one::<PathBuf>("file");
     let result = index_type.to_string();
+    let goat_url = utils::goat_url(matches)?;
     let summarise_values_by = "count";
     // add in exclusion of missing and ancestral values by default, but allow the user
     // to toggle this on the command line
@@ -143,10 +145,7 @@ pub fn process_cli_args(
     let assembly_btk = *matches.get_one::<bool>("assembly-btk").unwrap_or(&false);
 
     if print_expression {
-        match index_type {
-            IndexType::Taxon => expression::print_variable_data(&GOAT_TAXON_VARIABLE_DATA),
-            IndexType::Assembly => expression::print_variable_data(&GOAT_ASSEMBLY_VARIABLE_DATA),
-        }
+        expression::print_variable_data(catalogue::variable_data(index_type));
         return Ok(CliAction::PrintedAndExit);
     }
 
@@ -183,14 +182,6 @@ pub fn process_cli_args(
         assembly_btk,
     };
 
-    if size as usize > *UPPER_CLI_SIZE_LIMIT {
-        let limit_string = utils::pretty_print_usize(*UPPER_CLI_SIZE_LIMIT);
-        return Err(Error::new(ErrorKind::GenericCli(format!(
-            "searches with more than {} results are not currently supported.",
-            limit_string
-        ))));
-    }
-
     // tree includes all descendents of a node
     let tax_tree = match (tax_tree_enum, tax_lineage_enum) {
         (TaxType::Tree, TaxType::Name) => "tree",
@@ -231,28 +222,49 @@ pub fn process_cli_args(
         },
     }
 
-    let url_vector_api = url::make_goat_urls(
-        api,
-        &url_vector,
-        &GOAT_URL,
-        tax_tree,
-        include_estimates,
-        // check again whether to include
-        // raw values in `assembly` index.
-        taxon_include_raw_values,
-        exclude,
-        summarise_values_by,
-        &result,
-        &TAXONOMY,
-        size,
-        ranks,
-        fields,
-        variable_string,
-        &expression,
-        &tax_rank,
-        unique_ids,
-        index_type,
-    )?;
+    // a set of URLs for each branch of an expression using OR
+    let branches = expressions.len();
+    let mut branch_urls = Vec::new();
+    for (branch, expression) in expressions.iter().enumerate() {
+        let branch_ids = match branches {
+            1 => unique_ids.clone(),
+            _ => unique_ids
+                .iter()
+                .map(|id| format!("{}_{}", id, branch))
+                .collect(),
+        };
+        branch_urls.push(url::make_goat_urls(
+            api,
+            &url_vector,
+            &goat_url,
+            tax_tree,
+            include_estimates,
+            // check again whether to include
+            // raw values in `assembly` index.
+            taxon_include_raw_values,
+            exclude,
+            summarise_values_by,
+            &result,
+            &TAXONOMY,
+            size,
+            ranks,
+            fields,
+            variable_string,
+            expression,
+            &tax_rank,
+            branch_ids,
+            index_type,
+        )?);
+    }
+    // keep the queries for each taxon together
+    let mut taxa = Vec::new();
+    let mut url_vector_api = Vec::new();
+    for (index, taxon) in url_vector.iter().enumerate() {
+        for urls in &branch_urls {
+            taxa.push(taxon.clone());
+            url_vector_api.push(urls[index].clone());
+        }
+    }
 
     if print_url {
         for (index, url) in url_vector_api.iter().enumerate() {
@@ -261,7 +273,7 @@ pub fn process_cli_args(
         return Ok(CliAction::PrintedAndExit);
     } else if print_goat_ui_url {
         for (index, url) in url_vector_api.iter().enumerate() {
-            let new_url = url.replace("api/v2/", "");
+            let new_url = utils::goat_ui_url(url, &goat_url);
             println!("{}.\tGoaT API URL: {}", index, new_url);
         }
         return Ok(CliAction::PrintedAndExit);
@@ -269,7 +281,8 @@ pub fn process_cli_args(
 
     Ok(CliAction::Continue {
         size,
-        taxa: url_vector,
+        taxa,
         urls: url_vector_api,
+        branches,
     })
 }
@@ -0,0 +1,424 @@
+use crate::error::{Error, ErrorKind, Result};
+use crate::utils::catalogue::variable_data;
+use crate::utils::expression::TypeOf;
+use crate::IndexType;
+
+use arrow_array::builder::{
+    Date32Builder, Float32Builder, Float64Builder, Int16Builder, Int32Builder, Int64Builder,
+    ListBuilder, StringBuilder,
+};
+use arrow_array::{ArrayRef, RecordBatch};
+use arrow_schema::{DataType, Field, Schema, SchemaRef};
+use parquet::arrow::ArrowWriter;
+use serde_json::Value;
+use std::io::Write;
+use std::sync::Arc;
+use url::Url;
+
+/// The type of a column in a typed export of search results,
+/// taken from the [`TypeOf`] of each GoaT variable.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ColumnType {
+    /// Signed 16 bit int.
+    Short,
+    /// Signed 32 bit int.
+    Integer,
+    /// Signed 64 bit int.
+    Long,
+    /// Double precision float, for one and two decimal place variables.
+    Float,
+    /// Single precision float, for half float variables.
+    HalfFloat,
+    /// A date.
+    Date,
+    /// A list of keywords. GoaT keyword variables can hold more than
+    /// one value, so these are always lists.
+    Keyword,
+    /// Plain text, for identifiers, names and anything uncatalogued.
+    Text,
+}
+
+impl ColumnType {
+    /// The column type for a GoaT variable type.
+    pub fn from_type_of(type_of: &TypeOf) -> Self {
+        match type_of {
+            TypeOf::Long => ColumnType::Long,
+            TypeOf::Short => ColumnType::Short,
+            TypeOf::Integer => ColumnType::Integer,
+            TypeOf::OneDP | TypeOf::TwoDP => ColumnType::Float,
+            TypeOf::HalfFloat => ColumnType::HalfFloat,
+            TypeOf::Date => ColumnType::Date,
+            TypeOf::Keyword(_) => ColumnType::Keyword,
+            TypeOf::None => ColumnType::Text,
+        }
+    }
+
+    /// The Arrow data type of this column.
+    pub fn data_type(&self) -> DataType {
+        match self {
+            ColumnType::Short => DataType::Int16,
+            ColumnType::Integer => DataType::Int32,
+            ColumnType::Long => DataType::Int64,
+            ColumnType::Float => DataType::Float64,
+            ColumnType::HalfFloat => DataType::Float32,
+            ColumnType::Date => DataType::Date32,
+            ColumnType::Keyword => {
+                DataType::List(Arc::new(Field::new("item", DataType::Utf8, true)))
+            }
+            ColumnType::Text => DataType::Utf8,
+        }
+    }
+}
+
+/// Where the value of a column is found in a search record
+/// (see [`crate::utils::output::search_records`]).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ColumnSource {
+    /// A top level key, e.g. `taxon_id`.
+    Record(String),
+    /// The scientific name at a rank from `--ranks`.
+    Rank(String),
+    /// The value of a GoaT variable under `fields`.
+    Field(String),
+}
+
+/// A single typed column of a search export.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Column {
+    /// The column name.
+    pub name: String,
+    /// Where to find the value in a record.
+    pub source: ColumnSource,
+    /// The type of the column.
+    pub column_type: ColumnType,
+}
+
+impl Column {
+    /// A column of a top level key in a record.
+    pub fn record(name: &str, column_type: ColumnType) -> Self {
+        Self {
+            name: name.to_string(),
+            source: ColumnSource::Record(name.to_string()),
+            column_type,
+        }
+    }
+
+    /// Get the value of this column from a record, which is
+    /// `Value::Null` if missing.
+    pub fn value<'a>(&self, record: &'a Value) -> &'a Value {
+        match &self.source {
+            ColumnSource::Record(key) => &record[key],
+            ColumnSource::Rank(rank) => &record["ranks"][rank]["scientific_name"],
+            ColumnSource::Field(field) => &record["fields"][field]["value"],
+        }
+    }
+}
+
+/// Work out the typed columns of a search from its API URL.
+///
+/// The identifier columns come first, then a column for each rank
+/// in `ranks`, then a column for each variable in `fields`. Variables
+/// are typed from the variable data of the index (see
+/// [`variable_data`]). The `:direct`/`:ancestor`/`:descendant`
+/// variants share the type of their variable.
+pub fn search_columns(url: &str, index_type: IndexType) -> Result<Vec<Column>> {
+    let url = Url::parse(url)
+        .map_err(|e| Error::new(ErrorKind::GenericCli(format!("could not parse URL: {}", e))))?;
+
+    let ids: &[&str] = match index_type {
+        IndexType::Taxon => &["search_query", "taxon_id", "scientific_name", "taxon_rank"],
+        IndexType::Assembly => &["search_query", "assembly_id", "taxon_id", "scientific_name"],
+    };
+    let mut columns = ids
+        .iter()
+        .map(|id| Column::record(id, ColumnType::Text))
+        .collect::<Vec<Column>>();
+
+    let variable_data = variable_data(index_type);
+
+    for (key, value) in url.query_pairs() {
+        match &*key {
+            "ranks" => {
+                columns.extend(
+                    value
+                        .split(',')
+                        .filter(|e| !e.is_empty())
+                        .map(|rank| Column {
+                            name: rank.to_string(),
+                            source: ColumnSource::Rank(rank.to_string()),
+                            column_type: ColumnType::Text,
+                        }),
+                )
+            }
+            "fields" => columns.extend(value.split(',').filter(|e| !e.is_empty()).map(|field| {
+                let variable = field.split(':').next().unwrap_or(field);
+                let column_type = variable_data
+                    .get(variable)
+                    .map(|v| ColumnType::from_type_of(&v.type_of))
+                    .unwrap_or(ColumnType::Text);
+                Column {
+                    name: field.to_string(),
+                    source: ColumnSource::Field(field.to_string()),
+                    column_type,
+                }
+            })),
+            _ => (),
+        }
+    }
+
+    Ok(columns)
+}
+
+/// Number of days since the Unix epoch for a date formatted
+/// `YYYY`, `YYYY-MM` or `YYYY-MM-DD` (anything after the day,
+/// such as a time, is ignored).
+pub fn days_since_epoch(date: &str) -> Option<i32> {
+    let mut parts = date.get(..10).unwrap_or(date).split('-');
+    let year = parts.next()?.parse::<i64>().ok()?;
+    let month = parts.next().map_or(Some(1), |m| m.parse::<i64>().ok())?;
+    let day = parts.next().map_or(Some(1), |d| d.parse::<i64>().ok())?;
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+
+    // from Howard Hinnant's `days_from_civil`
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let doy = (153 * (month + if month > 2 { -3 } else { 9 }) + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    i32::try_from(era * 146097 + doe - 719468).ok()
+}
+
+/// A number, which the API may return as a string.
+pub(crate) fn as_f64(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => s.parse().ok(),
+        _ => None,
+    }
+}
+
+/// An integer. Non-integer values (e.g. a median of an
+/// integer variable) are rounded.
+pub(crate) fn as_i64(value: &Value) -> Option<i64> {
+    match value {
+        Value::Number(n) => n.as_i64().or_else(|| n.as_f64().map(|f| f.round() as i64)),
+        _ => as_f64(value).map(|f| f.round() as i64),
+    }
+}
+
+/// A value as text, without the quotes of a JSON string.
+pub(crate) fn as_text(value: &Value) -> Option<String> {
+    match value {
+        Value::Null => None,
+        Value::String(s) => Some(s.clone()),
+        other => Some(other.to_string()),
+    }
+}
+
+/// Build the Arrow array for a single column of records.
+fn build_array(column: &Column, records: &[Value]) -> ArrayRef {
+    let values = records.iter().map(|record| column.value(record));
+    match column.column_type {
+        ColumnType::Short => {
+            let mut builder = Int16Builder::new();
+            values
+                .for_each(|v| builder.append_option(as_i64(v).and_then(|i| i16::try_from(i).ok())));
+            Arc::new(builder.finish())
+        }
+        ColumnType::Integer => {
+            let mut builder = Int32Builder::new();
+            values
+                .for_each(|v| builder.append_option(as_i64(v).and_then(|i| i32::try_from(i).ok())));
+            Arc::new(builder.finish())
+        }
+        ColumnType::Long => {
+            let mut builder = Int64Builder::new();
+            values.for_each(|v| builder.append_option(as_i64(v)));
+            Arc::new(builder.finish())
+        }
+        ColumnType::Float => {
+            let mut builder = Float64Builder::new();
+            values.for_each(|v| builder.append_option(as_f64(v)));
+            Arc::new(builder.finish())
+        }
+        ColumnType::HalfFloat => {
+            let mut builder = Float32Builder::new();
+            values.for_each(|v| builder.append_option(as_f64(v).map(|f| f as f32)));
+            Arc::new(builder.finish())
+        }
+        ColumnType::Date => {
+            let mut builder = Date32Builder::new();
+            values.for_each(|v| builder.append_option(v.as_str().and_then(days_since_epoch)));
+            Arc::new(builder.finish())
+        }
+        ColumnType::Keyword => {
+            let mut builder = ListBuilder::new(StringBuilder::new());
+            for value in values {
+                match value {
+                    Value::Null => builder.append_null(),
+                    Value::Array(keywords) => {
+                        for keyword in keywords {
+                            builder.values().append_option(as_text(keyword));
+                        }
+                        builder.append(true);
+                    }
+                    keyword => {
+                        builder.values().append_option(as_text(keyword));
+                        builder.append(true);
+                    }
+                }
+            }
+            Arc::new(builder.finish())
+        }
+        ColumnType::Text => {
+            let mut builder = StringBuilder::new();
+            values.for_each(|v| builder.append_option(as_text(v)));
+            Arc::new(builder.finish())
+        }
+    }
+}
+
+/// Writes records out as a typed Parquet file.
+///
+/// Each call to [`ParquetWriter::write_records`] is written as a
+/// record batch, and the file is completed by [`ParquetWriter::finish`].
+pub struct ParquetWriter<W: Write + Send> {
+    writer: ArrowWriter<W>,
+    schema: SchemaRef,
+    columns: Vec<Column>,
+}
+
+impl<W: Write + Send> ParquetWriter<W> {
+    /// Constructor for [`ParquetWriter`].
+    pub fn new(writer: W, columns: Vec<Column>) -> Result<Self> {
+        let schema = Arc::new(Schema::new(
+            columns
+                .iter()
+                .map(|c| Field::new(&c.name, c.column_type.data_type(), true))
+                .collect::<Vec<Field>>(),
+        ));
+        let writer = ArrowWriter::try_new(writer, schema.clone(), None)?;
+        Ok(Self {
+            writer,
+            schema,
+            columns,
+        })
+    }
+
+    /// Write a batch of records.
+    pub fn write_records(&mut self, records: &[Value]) -> Result<()> {
+        if records.is_empty() {
+            return Ok(());
+        }
+        let arrays = self
+            .columns
+            .iter()
+            .map(|column| build_array(column, records))
+            .collect::<Vec<ArrayRef>>();
+        let batch = RecordBatch::try_new(self.schema.clone(), arrays)?;
+        self.writer.write(&batch)?;
+        Ok(())
+    }
+
+    /// Finish writing, completing the Parquet footer.
+    pub fn finish(self) -> Result<()> {
+        let mut writer = self.writer.into_inner()?;
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow_array::{Array, Date32Array, Int64Array, ListArray, StringArray};
+    use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+    use serde_json::json;
+
+    const URL: &str = "https://goat.genomehubs.org/api/v2/search?query=tax_tree%28Aves%29&size=10&ranks=genus%2Cfamily&fields=genome_size%2Cgenome_size%3Adirect%2Cassembly_level%2Cassembly_date%2Cmade_up";
+
+    #[test]
+    fn test_search_columns_are_typed_from_variable_data() {
+        let columns = search_columns(URL, IndexType::Taxon).unwrap();
+        let names_types = columns
+            .iter()
+            .map(|c| (c.name.as_str(), c.column_type))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            names_types,
+            vec![
+                ("search_query", ColumnType::Text),
+                ("taxon_id", ColumnType::Text),
+                ("scientific_name", ColumnType::Text),
+                ("taxon_rank", ColumnType::Text),
+                ("genus", ColumnType::Text),
+                ("family", ColumnType::Text),
+                ("genome_size", ColumnType::Long),
+                ("genome_size:direct", ColumnType::Long),
+                ("assembly_level", ColumnType::Keyword),
+                ("assembly_date", ColumnType::Date),
+                ("made_up", ColumnType::Text),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_days_since_epoch() {
+        assert_eq!(days_since_epoch("1970-01-01"), Some(0));
+        assert_eq!(days_since_epoch("2000-03-01"), Some(11017));
+        assert_eq!(days_since_epoch("2021-05-12T10:00:00.000Z"), Some(18759));
+        assert_eq!(days_since_epoch("2021"), days_since_epoch("2021-01-01"));
+        assert_eq!(days_since_epoch("not a date"), None);
+    }
+
+    #[test]
+    fn test_parquet_writer_round_trip() {
+        let columns = search_columns(URL, IndexType::Taxon).unwrap();
+        let records = vec![
+            json!({
+                "search_query": "Aves",
+                "taxon_id": "9031",
+                "ranks": {"genus": {"scientific_name": "Gallus"}},
+                "fields": {
+                    "genome_size": {"value": 1065365434u64},
+                    "assembly_level": {"value": ["chromosome", "scaffold"]},
+                    "assembly_date": {"value": "2021-05-12"}
+                }
+            }),
+            json!({"search_query": "Aves", "taxon_id": "8839"}),
+        ];
+
+        let mut out = Vec::new();
+        let mut writer = ParquetWriter::new(&mut out, columns).unwrap();
+        writer.write_records(&records).unwrap();
+        writer.finish().unwrap();
+
+        let mut reader = ParquetRecordBatchReaderBuilder::try_new(bytes::Bytes::from(out))
+            .unwrap()
+            .build()
+            .unwrap();
+        let batch = reader.next().unwrap().unwrap();
+        assert_eq!(batch.num_rows(), 2);
+
+        let genus = batch.column_by_name("genus").unwrap();
+        let genus = genus.as_any().downcast_ref::<StringArray>().unwrap();
+        assert_eq!(genus.value(0), "Gallus");
+        assert!(genus.is_null(1));
+
+        let size = batch.column_by_name("genome_size").unwrap();
+        let size = size.as_any().downcast_ref::<Int64Array>().unwrap();
+        assert_eq!(size.value(0), 1065365434);
+        assert!(size.is_null(1));
+
+        let level = batch.column_by_name("assembly_level").unwrap();
+        let level = level.as_any().downcast_ref::<ListArray>().unwrap();
+        assert_eq!(level.value(0).len(), 2);
+        assert!(level.is_null(1));
+
+        let date = batch.column_by_name("assembly_date").unwrap();
+        let date = date.as_any().downcast_ref::<Date32Array>().unwrap();
+        assert_eq!(date.value(0), 18759);
+    }
+}
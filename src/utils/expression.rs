@@ -224,12 +224,384 @@ pub fn print_variable_data(data: &BTreeMap<&'static str, Variable<'static>>) {
     println!("{}", table_string);
 }
 
+/// The most queries a single expression can expand to,
+/// when OR can't be written in the API's syntax.
+const EXPRESSION_BRANCH_LIMIT: usize = 16;
+
+/// A token of a CLI expression.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    /// A comparison, e.g. `genome_size > 1000`, or a plain variable.
+    Clause(String),
+    And,
+    Or,
+    Not,
+    LParen,
+    RParen,
+}
+
+/// Split a CLI expression into [`Token`]s.
+///
+/// `AND`, `OR` and `NOT` must be upper case whole words, and `&&`/`||`
+/// can also be used. Parentheses group clauses, unless they directly
+/// follow a name, as in `max(genome_size)`, where they are part of
+/// the clause.
+fn tokenize(input: &str) -> Result<Vec<Token>> {
+    let chars = input.chars().collect::<Vec<char>>();
+    let mut tokens = Vec::new();
+    let mut clause = String::new();
+
+    fn flush(clause: &mut String, tokens: &mut Vec<Token>) {
+        let trimmed = clause.trim();
+        if !trimmed.is_empty() {
+            tokens.push(Token::Clause(trimmed.to_string()));
+        }
+        clause.clear();
+    }
+
+    let is_boundary = |c: Option<&char>| match c {
+        None => true,
+        Some(c) => c.is_whitespace() || *c == '(' || *c == ')',
+    };
+
+    let mut i = 0;
+    while i < chars.len() {
+        let rest = &chars[i..];
+        let starts_word = i == 0 || is_boundary(chars.get(i - 1));
+        let keyword = ["AND", "OR", "NOT"].into_iter().find(|k| {
+            starts_word
+                && rest.iter().take(k.len()).copied().eq(k.chars())
+                && is_boundary(rest.get(k.len()))
+        });
+
+        if let Some(k) = keyword {
+            flush(&mut clause, &mut tokens);
+            tokens.push(match k {
+                "AND" => Token::And,
+                "OR" => Token::Or,
+                _ => Token::Not,
+            });
+            i += k.len();
+        } else if rest.starts_with(&['&', '&']) || rest.starts_with(&['|', '|']) {
+            flush(&mut clause, &mut tokens);
+            tokens.push(match rest[0] {
+                '&' => Token::And,
+                _ => Token::Or,
+            });
+            i += 2;
+        } else if chars[i] == '(' {
+            // a function call, e.g. `length(long_list)`
+            if clause.ends_with(|c: char| c.is_alphanumeric() || c == '_') {
+                let close = rest.iter().position(|c| *c == ')').ok_or_else(|| {
+                    Error::new(ErrorKind::Expression(format!(
+                        "unclosed parenthesis after \"{}\".",
+                        clause.trim()
+                    )))
+                })?;
+                clause.extend(&rest[..=close]);
+                i += close + 1;
+            } else {
+                flush(&mut clause, &mut tokens);
+                tokens.push(Token::LParen);
+                i += 1;
+            }
+        } else if chars[i] == ')' {
+            flush(&mut clause, &mut tokens);
+            tokens.push(Token::RParen);
+            i += 1;
+        } else {
+            clause.push(chars[i]);
+            i += 1;
+        }
+    }
+    flush(&mut clause, &mut tokens);
+
+    Ok(tokens)
+}
+
+/// The syntax tree of a CLI expression.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Expr {
+    Clause(String),
+    Not(Box<Expr>),
+    And(Vec<Expr>),
+    Or(Vec<Expr>),
+}
+
+/// A recursive descent parser over [`Token`]s. `NOT` binds
+/// tightest, then `AND`, then `OR`.
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn parse(mut self) -> Result<Expr> {
+        let expr = self.or()?;
+        match self.peek() {
+            None => Ok(expr),
+            Some(Token::RParen) => Err(Error::new(ErrorKind::Expression(
+                "unmatched closing parenthesis.".to_string(),
+            ))),
+            Some(t) => Err(Error::new(ErrorKind::Expression(format!(
+                "unexpected {:?} in expression; are the clauses joined by AND or OR?",
+                t
+            )))),
+        }
+    }
+
+    fn or(&mut self) -> Result<Expr> {
+        let mut children = vec![self.and()?];
+        while self.peek() == Some(&Token::Or) {
+            self.position += 1;
+            children.push(self.and()?);
+        }
+        Ok(match children.len() {
+            1 => children.remove(0),
+            _ => Expr::Or(children),
+        })
+    }
+
+    fn and(&mut self) -> Result<Expr> {
+        let mut children = vec![self.not()?];
+        while self.peek() == Some(&Token::And) {
+            self.position += 1;
+            children.push(self.not()?);
+        }
+        Ok(match children.len() {
+            1 => children.remove(0),
+            _ => Expr::And(children),
+        })
+    }
+
+    fn not(&mut self) -> Result<Expr> {
+        let token = self.peek().cloned();
+        self.position += 1;
+        match token {
+            Some(Token::Not) => Ok(Expr::Not(Box::new(self.not()?))),
+            Some(Token::LParen) => {
+                let expr = self.or()?;
+                match self.peek() {
+                    Some(Token::RParen) => {
+                        self.position += 1;
+                        Ok(expr)
+                    }
+                    _ => Err(Error::new(ErrorKind::Expression(
+                        "unclosed parenthesis in expression.".to_string(),
+                    ))),
+                }
+            }
+            Some(Token::Clause(c)) => Ok(Expr::Clause(c)),
+            _ => Err(Error::new(ErrorKind::Expression(
+                "must be in the format: <variable> <operator> <value> AND ...".to_string(),
+            ))),
+        }
+    }
+}
+
+/// A single validated clause of an expression.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Leaf {
+    variable: String,
+    /// `None` for a plain variable, i.e. the variable has a value.
+    operator: Option<Operator>,
+    value: String,
+    keyword: bool,
+}
+
+impl Leaf {
+    /// The opposite of this clause, for pushing `NOT` down the tree.
+    fn negate(mut self) -> Result<Self> {
+        let operator = self.operator.ok_or_else(|| {
+            Error::new(ErrorKind::Expression(format!(
+                "NOT can't be applied to \"{}\" on its own; compare it to a value instead.",
+                self.variable
+            )))
+        })?;
+        self.operator = Some(match operator {
+            Operator::Lt => Operator::GtEq,
+            Operator::LtEq => Operator::Gt,
+            Operator::Gt => Operator::LtEq,
+            Operator::GtEq => Operator::Lt,
+            Operator::Eq | Operator::EqEq => Operator::NotEq,
+            // `=!` is already a negation
+            Operator::NotEq | Operator::EqBang => Operator::Eq,
+        });
+        Ok(self)
+    }
+
+    /// Can these two clauses be written as a single clause, as
+    /// `variable = a OR variable = b` is `variable = a,b`?
+    fn merge(&self, other: &Leaf) -> Option<Leaf> {
+        let is_eq = |o: Option<Operator>| matches!(o, Some(Operator::Eq | Operator::EqEq));
+        if self.keyword
+            && other.keyword
+            && self.variable == other.variable
+            && is_eq(self.operator)
+            && is_eq(other.operator)
+        {
+            let mut merged = self.clone();
+            merged.value = format!("{},{}", self.value, other.value);
+            Some(merged)
+        } else {
+            None
+        }
+    }
+}
+
+impl fmt::Display for Leaf {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.operator {
+            Some(o) => write!(f, "{} {} {}", self.variable, o.as_str(), self.value),
+            None => write!(f, "{}", self.variable),
+        }
+    }
+}
+
+/// Expand an expression into OR'd branches of AND'd clauses
+/// (disjunctive normal form), pushing `NOT` down to the clauses.
+fn expand(
+    expr: &Expr,
+    negated: bool,
+    leaf: &dyn Fn(&str) -> Result<Leaf>,
+) -> Result<Vec<Vec<Leaf>>> {
+    let too_many = |n: usize| {
+        Error::new(ErrorKind::Expression(format!(
+            "the expression expands to {} queries, the limit is {}. Commas between categories operate like the OR keyword.",
+            n, EXPRESSION_BRANCH_LIMIT
+        )))
+    };
+
+    match (expr, negated) {
+        (Expr::Clause(c), false) => Ok(vec![vec![leaf(c)?]]),
+        (Expr::Clause(c), true) => Ok(vec![vec![leaf(c)?.negate()?]]),
+        (Expr::Not(e), _) => expand(e, !negated, leaf),
+        // AND, or NOT over OR
+        (Expr::And(children), false) | (Expr::Or(children), true) => {
+            let mut branches: Vec<Vec<Leaf>> = vec![vec![]];
+            for child in children {
+                let child_branches = expand(child, negated, leaf)?;
+                if branches.len() * child_branches.len() > EXPRESSION_BRANCH_LIMIT {
+                    return Err(too_many(branches.len() * child_branches.len()));
+                }
+                branches = branches
+                    .iter()
+                    .flat_map(|b| {
+                        child_branches.iter().map(move |c| {
+                            let mut branch = b.clone();
+                            branch.extend(c.iter().cloned());
+                            branch
+                        })
+                    })
+                    .collect();
+            }
+            Ok(branches)
+        }
+        // OR, or NOT over AND
+        (Expr::Or(children), false) | (Expr::And(children), true) => {
+            let mut branches = Vec::new();
+            for child in children {
+                branches.extend(expand(child, negated, leaf)?);
+            }
+            let branches = merge_branches(branches);
+            if branches.len() > EXPRESSION_BRANCH_LIMIT {
+                return Err(too_many(branches.len()));
+            }
+            Ok(branches)
+        }
+    }
+}
+
+/// Merge branches that differ only by a keyword clause on the same
+/// variable, so they can be sent as a single query.
+fn merge_branches(mut branches: Vec<Vec<Leaf>>) -> Vec<Vec<Leaf>> {
+    let mut i = 0;
+    while i < branches.len() {
+        let mut j = i + 1;
+        while j < branches.len() {
+            let (a, b) = (&branches[i], &branches[j]);
+            let differences = a
+                .iter()
+                .zip(b.iter())
+                .enumerate()
+                .filter(|(_, (x, y))| x != y)
+                .collect::<Vec<_>>();
+            let merged = match (a.len() == b.len(), differences.as_slice()) {
+                (true, [(index, (x, y))]) => x.merge(y).map(|leaf| (*index, leaf)),
+                _ => None,
+            };
+            match merged {
+                Some((index, leaf)) => {
+                    branches[i][index] = leaf;
+                    branches.remove(j);
+                }
+                None => j += 1,
+            }
+        }
+        i += 1;
+    }
+    branches
+}
+
+/// Multipliers for the size suffixes accepted on span and size
+/// variables, matched case insensitively.
+const SIZE_SUFFIXES: [(&str, f64); 8] = [
+    ("kb", 1e3),
+    ("k", 1e3),
+    ("mb", 1e6),
+    ("m", 1e6),
+    ("gb", 1e9),
+    ("g", 1e9),
+    ("tb", 1e12),
+    ("t", 1e12),
+];
+
+/// Normalise a value given with units to the bare number the API
+/// expects. Span and size variables typed as `Long` or `Integer`
+/// (e.g. `genome_size`, `assembly_span`, `contig_n50`) take the
+/// [`SIZE_SUFFIXES`] `k`/`kb`, `m`/`mb`, `g`/`gb` and `t`/`tb`, in
+/// any case, so `1.5Gb` and `1.5g` are both `1500000000`. `Short`
+/// variables are counts, never sizes, whatever they are called.
+/// Percentage variables (e.g. `gc_percent`) can be given with a
+/// `%`. Any other value is returned as it is.
+fn normalise_units(variable: &str, type_of: &TypeOf, value: &str) -> Result<String> {
+    let is_size = ["span", "size", "n50"].iter().any(|e| variable.contains(e))
+        && matches!(type_of, TypeOf::Long | TypeOf::Integer);
+    let is_percent = variable.ends_with("_percent")
+        && matches!(type_of, TypeOf::OneDP | TypeOf::TwoDP | TypeOf::HalfFloat);
+
+    if is_percent {
+        if let Some(number) = value.strip_suffix('%') {
+            return Ok(number.trim().to_string());
+        }
+    }
+
+    if is_size {
+        let lower = value.to_lowercase();
+        for (suffix, multiplier) in SIZE_SUFFIXES {
+            if let Some(number) = lower.strip_suffix(suffix) {
+                let number = number.trim().parse::<f64>().map_err(|_| {
+                    Error::new(ErrorKind::Expression(format!(
+                        "for variable \"{variable}\", could not read \"{value}\" as a size. Use e.g. 1.5G, 500Mb or 20kb."
+                    )))
+                })?;
+                return Ok(format!("{}", (number * multiplier).round() as i64));
+            }
+        }
+    }
+
+    Ok(value.to_string())
+}
+
 /// The CLI expression which needs to be parsed.
 #[derive(Debug)]
 pub struct CLIexpression<'a> {
     pub inner: &'a str,
     pub length: usize, // these queries can't be crazy long.
-    pub expression: Vec<&'a str>,
 }
 
 impl<'a> CLIexpression<'a> {
@@ -238,37 +610,38 @@ impl<'a> CLIexpression<'a> {
         Self {
             inner: string,
             length: string.len(),
-            expression: Vec::new(),
         }
     }
 
-    /// The initial split on the keyword `AND`.
-    fn split(&self) -> Self {
-        let mut res_vec = Vec::new();
-        let re = Regex::new("AND").unwrap();
-        let splitter = SplitCaptures::new(&re, self.inner);
-        for state in splitter {
-            let el = match state {
-                SplitState::Unmatched(s) => s,
-                SplitState::Captured(s) => s.get(0).map_or("", |m| m.as_str()),
-            };
-            res_vec.push(el);
-        }
-        Self {
-            inner: self.inner,
-            length: self.length,
-            expression: res_vec,
+    /// Parse a [`CLIexpression`] that can be sent as a single query. Errors
+    /// if an `OR` can't be written in the API's syntax, see
+    /// [`CLIexpression::parse_branches`].
+    pub fn parse(
+        &mut self,
+        reference_data: &BTreeMap<&'static str, Variable<'static>>,
+    ) -> Result<String> {
+        let mut branches = self.parse_branches(reference_data)?;
+        match branches.len() {
+            1 => Ok(branches.remove(0)),
+            n => Err(Error::new(ErrorKind::Expression(format!(
+                "this expression needs {} separate queries, which is only supported by search and count.",
+                n
+            )))),
         }
     }
 
-    /// The main function which parses a [`CLIexpression`]. A bit of a
-    /// monster of a function. Might need cleaning up at some point.
-    // FIXME: this function can also be used in reports for the -x
-    // so let's make it more general.
-    pub fn parse(
+    /// Parse a [`CLIexpression`], which may use `AND`, `OR`, `NOT` and
+    /// parentheses, into one query string per `OR`'d branch. Each clause
+    /// is checked against the variables in `reference_data`.
+    ///
+    /// The API only supports `AND`, so `NOT` is applied to the operators
+    /// of the clauses, and `OR` between values of a keyword variable is
+    /// written with commas. Any other `OR` needs a query per branch, and
+    /// the results unioned.
+    pub fn parse_branches(
         &mut self,
         reference_data: &BTreeMap<&'static str, Variable<'static>>,
-    ) -> Result<String> {
+    ) -> Result<Vec<String>> {
         // TODO: what's an acceptable limit here?
         let expression_length_limit = 100;
         if self.length > expression_length_limit {
@@ -277,22 +650,12 @@ impl<'a> CLIexpression<'a> {
                 expression_length_limit
             ))));
         }
-        // we don't use &&
-        if self.inner.contains("&&") {
-            return Err(Error::new(ErrorKind::Expression(
-                "use AND keyword, not && for queries.".to_string(),
-            )));
-        }
         // we don't use 'contains'
         if self.inner.contains(" contains") {
             return Err(Error::new(ErrorKind::Expression(
                 "using the \"contains\" keyword is not yet supported.".to_string(),
             )));
         }
-        // we don't allow OR
-        if self.inner.contains("||") || self.inner.contains("OR") {
-            return Err(Error::new(ErrorKind::Expression("OR (or ||) keyword is not supported. Commas between categories operate like the OR keyword.".to_string())));
-        }
         // for the more general expression case, we want to include this
         if self.inner.contains("tax_name")
             || self.inner.contains("tax_tree")
@@ -307,17 +670,6 @@ impl<'a> CLIexpression<'a> {
             )));
         }
 
-        // essentially splitting on AND
-        let split_vec = &self.split();
-
-        let exp_vec = &split_vec.expression;
-
-        // split the expression vector into parts
-        let mut index = 0;
-        let exp_vec_len = exp_vec.len();
-        // parse into clauses
-        let mut clauses: Vec<String> = Vec::new();
-
         // regular expression splitter
         // precedence here matters
         let re = Regex::new(r"=!|!=|<=|<|==|=|>=|>").unwrap();
@@ -327,13 +679,41 @@ impl<'a> CLIexpression<'a> {
             )));
         }
 
-        // vector of variables to check against
-        let var_vec_check = &reference_data
+        let tree = Parser {
+            tokens: tokenize(self.inner)?,
+            position: 0,
+        }
+        .parse()?;
+
+        let checks = ClauseChecks::new(reference_data);
+        let leaf = |clause: &str| checks.parse_clause(clause, &re);
+        let branches = expand(&tree, false, &leaf)?;
+
+        Ok(branches
             .iter()
-            .map(|(e, _)| *e)
-            .collect::<Vec<&str>>();
-        // we can also create another vector of variables
-        // with the appropriate max/min attached.
+            .map(|branch| {
+                let clauses = branch.iter().map(|l| l.to_string()).collect::<Vec<_>>();
+                format!(" AND {}", clauses.join(" AND "))
+            })
+            .collect())
+    }
+}
+
+/// The variables (and functions of variables) a clause
+/// of an expression is checked against.
+struct ClauseChecks<'r> {
+    reference_data: &'r BTreeMap<&'static str, Variable<'static>>,
+    // vector of variables to check against
+    var_vec_check: Vec<&'static str>,
+    // we can also create another vector of variables
+    // with the appropriate max/min attached.
+    var_vec_functions_check: Vec<String>,
+    // extracts the variable name from within a function, e.g. min(genome_size)
+    function_re: Regex,
+}
+
+impl<'r> ClauseChecks<'r> {
+    fn new(reference_data: &'r BTreeMap<&'static str, Variable<'static>>) -> Self {
         let var_vec_functions_check = {
             let mut collector = Vec::new();
             for (goat_var, el) in reference_data {
@@ -341,7 +721,7 @@ impl<'a> CLIexpression<'a> {
                     Function::None => {
                         // FIXME: this is a horrible hack. Not sure how expressions like this
                         // fit into the engine at the moment
-                        collector.push(format!("length(long_list)"));
+                        collector.push("length(long_list)".to_string());
                     }
                     Function::Some(f) => {
                         // FIXME: some functions like 'length' are not yet in the reference data.
@@ -356,193 +736,161 @@ impl<'a> CLIexpression<'a> {
             collector
         };
 
-        // loop over the expression vector
-        // splitting into further vectors
-        // to evaluate each argument.
-        loop {
-            if index == exp_vec_len {
-                break;
-            }
-            // expected to be in format
-            // variable <operator> number/enum
-            // OR a plain variable
-            let curr_el = exp_vec[index];
-
-            let mut curr_el_vec = Vec::new();
-            // split this on the operator
-            // do we need to check whether this operator actually exists?
-            // I can imagine that this will break down otherwise...
-            let splitter = SplitCaptures::new(&re, curr_el);
-
-            for state in splitter {
-                match state {
-                    SplitState::Unmatched(s) => {
-                        curr_el_vec.push(s);
-                    }
-                    SplitState::Captured(s) => {
-                        curr_el_vec.push(s.get(0).map_or("", |m| m.as_str()));
-                    }
-                };
-            }
+        Self {
+            reference_data,
+            var_vec_check: reference_data.keys().copied().collect(),
+            var_vec_functions_check,
+            function_re: Regex::new(r"\((.*?)\)").unwrap(),
+        }
+    }
 
-            match curr_el_vec.len() {
-                3 => {
-                    // trim strings
-                    // replace rogue quotes (not sure why this is happening now, but was not before...)
-                    // manually escape these...
-                    let variable = &curr_el_vec[0].trim().replace('\"', "").replace('\'', "")[..];
-                    let operator = Operator::parse(curr_el_vec[1])?;
-                    let operator_str = operator.as_str();
-
-                    let value = &curr_el_vec[2].trim().replace('\"', "").replace('\'', "")[..];
-
-                    if !var_vec_check.contains(&variable)
-                        && !var_vec_functions_check.contains(&variable.to_string())
-                    {
-                        // ew
-                        // just combining the min/max and normal variable vectors
-                        // into a single vector.
-                        let combined_checks = var_vec_check
-                            .iter()
-                            .map(|e| String::from(*e))
-                            .collect::<Vec<String>>()
-                            .iter()
-                            .chain(
-                                var_vec_functions_check
-                                    .iter()
-                                    .map(String::from)
-                                    .collect::<Vec<String>>()
-                                    .iter(),
-                            )
-                            .map(String::from)
-                            .collect::<Vec<String>>();
+    /// Check a single clause, expected to be in the format
+    /// `variable <operator> number/enum`, or a plain variable.
+    fn parse_clause(&self, clause: &str, re: &Regex) -> Result<Leaf> {
+        let mut curr_el_vec = Vec::new();
+        // split this on the operator
+        let splitter = SplitCaptures::new(re, clause);
 
-                        let var_vec_mean = did_you_mean(&combined_checks, variable);
+        for state in splitter {
+            match state {
+                SplitState::Unmatched(s) => {
+                    curr_el_vec.push(s);
+                }
+                SplitState::Captured(s) => {
+                    curr_el_vec.push(s.get(0).map_or("", |m| m.as_str()));
+                }
+            };
+        }
 
-                        if let Some(value) = var_vec_mean {
-                            return Err(Error::new(ErrorKind::Expression(format!(
-                                "in LHS you typed \"{}\" - did you mean \"{}\"?",
-                                variable, value
-                            ))));
-                        }
+        match curr_el_vec.len() {
+            3 => {
+                // trim strings
+                // replace rogue quotes (not sure why this is happening now, but was not before...)
+                // manually escape these...
+                let variable = &curr_el_vec[0].trim().replace(['\"', '\''], "")[..];
+                let operator = Operator::parse(curr_el_vec[1])?;
+
+                let value = &curr_el_vec[2].trim().replace(['\"', '\''], "")[..];
+
+                if !self.var_vec_check.contains(&variable)
+                    && !self.var_vec_functions_check.contains(&variable.to_string())
+                {
+                    // just combining the min/max and normal variable vectors
+                    // into a single vector.
+                    let combined_checks = self
+                        .var_vec_check
+                        .iter()
+                        .map(|e| String::from(*e))
+                        .chain(self.var_vec_functions_check.iter().cloned())
+                        .collect::<Vec<String>>();
+
+                    let var_vec_mean = did_you_mean(&combined_checks, variable);
+
+                    if let Some(value) = var_vec_mean {
+                        return Err(Error::new(ErrorKind::Expression(format!(
+                            "in LHS you typed \"{}\" - did you mean \"{}\"?",
+                            variable, value
+                        ))));
                     }
+                }
 
-                    // if min/max present, extract the variable name within the parentheses.
-                    let re = Regex::new(r"\((.*?)\)").unwrap();
-                    let keyword_enums =
-                        if var_vec_functions_check.contains(&variable.to_string()) {
-                            let extract_var = re
-                                .captures(variable)
-                                .and_then(|c| c.get(1))
-                                .map(|m| m.as_str())
-                                .ok_or_else(|| {
-                                    Error::new(ErrorKind::Expression(format!(
-                                        "failed to extract variable name from function expression: {}",
-                                        variable
-                                    )))
-                                })?;
-                            reference_data
-                                .get(extract_var)
-                                .ok_or_else(|| {
-                                    Error::new(ErrorKind::Expression(format!(
-                                        "variable \"{}\" not found in reference data",
-                                        extract_var
-                                    )))
-                                })
-                                .map(|v| &v.type_of)?
-                        } else {
-                            reference_data
-                                .get(variable)
-                                .ok_or_else(|| {
-                                    Error::new(ErrorKind::Expression(format!(
-                                        "variable \"{}\" not found in reference data",
-                                        variable
-                                    )))
-                                })
-                                .map(|v| &v.type_of)?
-                        };
-
-                    // if there are keywords, make sure they are a match
-                    match keyword_enums {
-                        TypeOf::Keyword(k) => {
-                            // split on commas here and trim (strip ! prefix for validation)
-                            let value_split_commas = value
-                                .split(',')
-                                .map(|e| {
-                                    let trimmed = e.trim();
-                                    trimmed.replace('!', "")
-                                })
-                                .collect::<Vec<String>>();
-
-                            // now check our keyword enums
-                            for val in &value_split_commas {
-                                // if 'val' starts with PRJEB, continue
-                                // as these are project identifiers and there are too many to enumerate.
-                                // or if val is numeric
-                                if val.starts_with("PRJEB") || val.parse::<i64>().is_ok() {
-                                    continue;
-                                }
+                // if min/max present, extract the variable name within the parentheses.
+                let lookup = if self.var_vec_functions_check.contains(&variable.to_string()) {
+                    self.function_re
+                        .captures(variable)
+                        .and_then(|c| c.get(1))
+                        .map(|m| m.as_str())
+                        .ok_or_else(|| {
+                            Error::new(ErrorKind::Expression(format!(
+                                "failed to extract variable name from function expression: {}",
+                                variable
+                            )))
+                        })?
+                } else {
+                    variable
+                };
+                let keyword_enums = self
+                    .reference_data
+                    .get(lookup)
+                    .ok_or_else(|| {
+                        Error::new(ErrorKind::Expression(format!(
+                            "variable \"{}\" not found in reference data",
+                            lookup
+                        )))
+                    })
+                    .map(|v| &v.type_of)?;
+
+                // if there are keywords, make sure they are a match
+                match keyword_enums {
+                    TypeOf::Keyword(k) => {
+                        // split on commas here and trim (strip ! prefix for validation)
+                        let value_split_commas = value
+                            .split(',')
+                            .map(|e| {
+                                let trimmed = e.trim();
+                                trimmed.replace('!', "")
+                            })
+                            .collect::<Vec<String>>();
 
-                                let possibilities =
-                                    k.iter().map(|e| String::from(*e)).collect::<Vec<_>>();
-                                let did_you_mean_str = did_you_mean(&possibilities, val);
-
-                                if let Some(value) = did_you_mean_str {
-                                    if value != *val {
-                                        return Err(Error::new(ErrorKind::Expression(format!(
-                                            "in (RHS you typed \"{}\" - did you mean \"{}\"?",
-                                            val, value
-                                        ))));
-                                    }
-                                }
+                        // now check our keyword enums
+                        for val in &value_split_commas {
+                            // if 'val' starts with PRJEB, continue
+                            // as these are project identifiers and there are too many to enumerate.
+                            // or if val is numeric
+                            if val.starts_with("PRJEB") || val.parse::<i64>().is_ok() {
+                                continue;
                             }
 
-                            // build clause — pass values as-is; the URL builder handles encoding
-                            let trimmed_values = value
-                                .split(',')
-                                .map(|e| e.trim().to_string())
-                                .collect::<Vec<String>>();
-                            let clause = format!(
-                                "{} {} {}",
-                                variable,
-                                operator_str,
-                                trimmed_values.join(",")
-                            );
-                            clauses.push(clause);
-                        }
-                        t => {
-                            // here can we type check input
-                            TypeOf::check(t, value, variable)?;
+                            let possibilities =
+                                k.iter().map(|e| String::from(*e)).collect::<Vec<_>>();
+                            let did_you_mean_str = did_you_mean(&possibilities, val);
 
-                            // build clause — pass values as-is; the URL builder handles encoding
-                            let clause = format!("{} {} {}", variable, operator_str, value);
-                            clauses.push(clause);
+                            if let Some(value) = did_you_mean_str {
+                                if value != *val {
+                                    return Err(Error::new(ErrorKind::Expression(format!(
+                                        "in (RHS you typed \"{}\" - did you mean \"{}\"?",
+                                        val, value
+                                    ))));
+                                }
+                            }
                         }
+
+                        // pass values as-is; the URL builder handles encoding
+                        let trimmed_values = value
+                            .split(',')
+                            .map(|e| e.trim().to_string())
+                            .collect::<Vec<String>>();
+                        Ok(Leaf {
+                            variable: variable.to_string(),
+                            operator: Some(operator),
+                            value: trimmed_values.join(","),
+                            keyword: true,
+                        })
                     }
-                }
-                1 => {
-                    // if this is AND, don't do anything
-                    if curr_el_vec[0].trim() != "AND" {
-                        let variable = curr_el_vec[0].trim().replace('\"', "").replace('\'', "");
-                        clauses.push(variable);
+                    t => {
+                        // e.g. 1.5Gb -> 1500000000, 40% -> 40
+                        let value = &normalise_units(lookup, t, value)?;
+                        // here can we type check input
+                        TypeOf::check(t, value, variable)?;
+
+                        Ok(Leaf {
+                            variable: variable.to_string(),
+                            operator: Some(operator),
+                            value: value.to_string(),
+                            keyword: false,
+                        })
                     }
                 }
-                _ => {
-                    return Err(Error::new(ErrorKind::Expression(
-                        "are the input variables or operands correct?".to_string(),
-                    )))
-                }
             }
-
-            index += 1;
-        }
-        // remove trailing AND%20
-        if clauses.is_empty() {
-            Err(Error::new(ErrorKind::Expression(
-                "must be in the format: <variable> <operator> <value> AND ...".to_string(),
-            )))
-        } else {
-            Ok(format!(" AND {}", clauses.join(" AND ")))
+            1 => Ok(Leaf {
+                variable: curr_el_vec[0].trim().replace(['\"', '\''], ""),
+                operator: None,
+                value: String::new(),
+                keyword: false,
+            }),
+            _ => Err(Error::new(ErrorKind::Expression(
+                "are the input variables or operands correct?".to_string(),
+            ))),
         }
     }
 }
@@ -604,7 +952,7 @@ impl<'r, 't> Iterator for SplitCaptures<'r, 't> {
 
 #[cfg(test)]
 mod tests {
-    use crate::utils::variable_data::GOAT_TAXON_VARIABLE_DATA;
+    use crate::utils::variable_data::{GOAT_ASSEMBLY_VARIABLE_DATA, GOAT_TAXON_VARIABLE_DATA};
 
     use super::*;
 
@@ -689,6 +1037,122 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_or_of_keywords_is_one_query() {
+        let expression =
+            "genome_size > 1000 AND (assembly_level = chromosome OR assembly_level = contig)";
+        let mut cli_exp = CLIexpression::new(expression);
+        let result = cli_exp.parse(&GOAT_TAXON_VARIABLE_DATA);
+        assert_eq!(
+            result.unwrap(),
+            " AND genome_size > 1000 AND assembly_level = chromosome,contig"
+        );
+    }
+
+    #[test]
+    fn test_or_of_variables_is_one_query_per_branch() {
+        let expression =
+            "genome_size > 1000 AND (assembly_level = chromosome OR contig_n50 > 1000000)";
+        let mut cli_exp = CLIexpression::new(expression);
+        let result = cli_exp.parse_branches(&GOAT_TAXON_VARIABLE_DATA);
+        assert_eq!(
+            result.unwrap(),
+            vec![
+                " AND genome_size > 1000 AND assembly_level = chromosome",
+                " AND genome_size > 1000 AND contig_n50 > 1000000"
+            ]
+        );
+        // a single query can't be made
+        let mut cli_exp = CLIexpression::new(expression);
+        assert!(cli_exp.parse(&GOAT_TAXON_VARIABLE_DATA).is_err());
+    }
+
+    #[test]
+    fn test_not_is_applied_to_operators() {
+        let expression = "NOT (genome_size > 1000 OR assembly_level = chromosome)";
+        let mut cli_exp = CLIexpression::new(expression);
+        let result = cli_exp.parse(&GOAT_TAXON_VARIABLE_DATA);
+        assert_eq!(
+            result.unwrap(),
+            " AND genome_size <= 1000 AND assembly_level != chromosome"
+        );
+    }
+
+    #[test]
+    fn test_not_of_eq_bang_is_eq() {
+        let mut cli_exp = CLIexpression::new("NOT bioproject =! PRJEB40665");
+        let result = cli_exp.parse(&GOAT_TAXON_VARIABLE_DATA);
+        assert_eq!(result.unwrap(), " AND bioproject = PRJEB40665");
+    }
+
+    #[test]
+    fn test_not_of_plain_variable_errors() {
+        let mut cli_exp = CLIexpression::new("genome_size > 1 AND NOT sequencing_status");
+        assert!(cli_exp.parse(&GOAT_TAXON_VARIABLE_DATA).is_err());
+    }
+
+    #[test]
+    fn test_unbalanced_parentheses_error() {
+        let mut cli_exp = CLIexpression::new("(genome_size > 1000");
+        assert!(cli_exp.parse(&GOAT_TAXON_VARIABLE_DATA).is_err());
+        let mut cli_exp = CLIexpression::new("genome_size > 1000)");
+        assert!(cli_exp.parse(&GOAT_TAXON_VARIABLE_DATA).is_err());
+    }
+
+    #[test]
+    fn test_tokenize_keeps_function_parentheses() {
+        let tokens = tokenize("NOT (max(genome_size) > 1 || c_value < 2)").unwrap();
+        assert_eq!(
+            tokens,
+            vec![
+                Token::Not,
+                Token::LParen,
+                Token::Clause("max(genome_size) > 1".into()),
+                Token::Or,
+                Token::Clause("c_value < 2".into()),
+                Token::RParen,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_size_suffixes_are_normalised() {
+        let expression = "genome_size > 1.5G AND contig_n50 >= 500kb AND assembly_span < 2 Gb";
+        let mut cli_exp = CLIexpression::new(expression);
+        let result = cli_exp.parse(&GOAT_TAXON_VARIABLE_DATA);
+        assert_eq!(
+            result.unwrap(),
+            " AND genome_size > 1500000000 AND contig_n50 >= 500000 AND assembly_span < 2000000000"
+        );
+    }
+
+    #[test]
+    fn test_percent_is_normalised() {
+        let mut cli_exp = CLIexpression::new("gc_percent >= 40.5%");
+        let result = cli_exp.parse(&GOAT_ASSEMBLY_VARIABLE_DATA);
+        assert_eq!(result.unwrap(), " AND gc_percent >= 40.5");
+    }
+
+    #[test]
+    fn test_suffixes_only_for_size_variables() {
+        // chromosome numbers aren't sizes
+        let mut cli_exp = CLIexpression::new("chromosome_number > 1k");
+        assert!(cli_exp.parse(&GOAT_TAXON_VARIABLE_DATA).is_err());
+        let mut cli_exp = CLIexpression::new("genome_size > lotsG");
+        assert!(cli_exp.parse(&GOAT_TAXON_VARIABLE_DATA).is_err());
+    }
+
+    #[test]
+    fn test_no_suffixes_for_short_variables() {
+        // the catalogue has no units, so a short named like a size
+        // must still be a plain number.
+        let value = normalise_units("sample_size", &TypeOf::Short, "1.5G").unwrap();
+        assert_eq!(value, "1.5G");
+        assert!(TypeOf::check(&TypeOf::Short, &value, "sample_size").is_err());
+        let value = normalise_units("sample_size", &TypeOf::Long, "1.5G").unwrap();
+        assert_eq!(value, "1500000000");
+    }
+
     #[test]
     fn test_operator_parse_gt() {
         let op = Operator::parse(">").unwrap();
@@ -1,15 +1,28 @@
 /// Parse the command line arguments
 /// for `goat-cli search` and `goat-cli count`.
 pub mod cli_matches;
+/// The GoaT variables for each index, fetched
+/// from the API at runtime and cached on disk.
+pub mod catalogue;
+/// Typed columns of search results, and a
+/// Parquet writer for them.
+pub mod columnar;
 /// Parse an expression from the `-e` flag from
 /// the CLI.
 pub mod expression;
+/// Write search and count results in the format
+/// chosen by `--output-format`.
+pub mod output;
+/// Write search results into a local SQLite
+/// database with `--sqlite`.
+pub mod sqlite;
 /// Parse taxon ranks from the `--tax-ranks`
 /// from the CLI.
 pub mod tax_ranks;
 /// Generate the URLs from the CLI.
 pub mod url;
 /// Utility functions used across `goat-cli`.
+#[allow(clippy::module_inception)]
 pub mod utils;
 /// Stored data for each of the variables used
 /// in `goat-cli`. Useful for comparing and debugging
@@ -0,0 +1,408 @@
+use crate::error::{Error, ErrorKind, Result};
+use serde_json::{Map, Value};
+use std::collections::{HashMap, HashSet};
+use std::io::{IsTerminal, Write};
+
+/// The output formats for `goat-cli search`, `goat-cli count` and the reports,
+/// chosen with `--output-format`.
+#[derive(Default, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// Tab separated values, as returned by the API.
+    #[default]
+    Tsv,
+    /// Comma separated values, as returned by the API.
+    Csv,
+    /// A single JSON array of records.
+    Json,
+    /// One JSON record per line.
+    Jsonl,
+    /// A typed Parquet file, see [`crate::utils::columnar`].
+    Parquet,
+}
+
+impl OutputFormat {
+    /// The formats a user can choose on the CLI.
+    pub const CLI_VALUES: [&'static str; 5] = ["tsv", "csv", "json", "jsonl", "parquet"];
+
+    /// Get the output format from the CLI, defaulting to TSV.
+    pub fn from_matches(matches: &clap::ArgMatches) -> Self {
+        match matches
+            .get_one::<String>("output-format")
+            .map(|e| e.as_str())
+        {
+            Some("csv") => OutputFormat::Csv,
+            Some("json") => OutputFormat::Json,
+            Some("jsonl") => OutputFormat::Jsonl,
+            Some("parquet") => OutputFormat::Parquet,
+            _ => OutputFormat::Tsv,
+        }
+    }
+
+    /// The `Accept` header to request this format from the API
+    /// with. JSON and Parquet records are built from the API's JSON.
+    pub fn accept_header(&self) -> &'static str {
+        match self {
+            OutputFormat::Tsv => "text/tab-separated-values",
+            OutputFormat::Csv => "text/csv",
+            OutputFormat::Json | OutputFormat::Jsonl | OutputFormat::Parquet => "application/json",
+        }
+    }
+
+    /// Parquet is binary, so refuse to write it to a terminal.
+    pub fn check_stdout(&self) -> Result<()> {
+        if *self == OutputFormat::Parquet && std::io::stdout().is_terminal() {
+            return Err(Error::new(ErrorKind::GenericCli(
+                "Parquet output is binary, redirect it to a file (e.g. `> out.parquet`)."
+                    .to_string(),
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Quote a CSV field if it holds a comma, quote or newline.
+pub(crate) fn csv_field(text: &str) -> String {
+    match text.contains([',', '"', '\n']) {
+        true => format!("\"{}\"", text.replace('"', "\"\"")),
+        false => text.to_string(),
+    }
+}
+
+/// Writes TSV (or CSV) responses out as they arrive from the API.
+///
+/// If multiple taxa are queried at once (or a search is paged), headers
+/// will return for every response. Only the header of the first response
+/// is written, so the output is a single table.
+pub struct TsvWriter<W: Write> {
+    writer: W,
+    header_written: bool,
+    /// Rows written so far for each search query, if duplicate
+    /// rows are skipped.
+    seen_rows: Option<HashMap<String, HashSet<String>>>,
+    /// The most rows to write for each search query, if duplicate
+    /// rows are skipped.
+    size: usize,
+}
+
+impl<W: Write> TsvWriter<W> {
+    /// Constructor for [`TsvWriter`].
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            header_written: false,
+            seen_rows: None,
+            size: usize::MAX,
+        }
+    }
+
+    /// Skip rows that have already been written, for when the
+    /// responses of an expression using `OR` are unioned. At most
+    /// `size` rows are written for each search query.
+    pub fn unique_rows(mut self, size: usize) -> Self {
+        self.seen_rows = Some(HashMap::new());
+        self.size = size;
+        self
+    }
+
+    /// Write a single TSV response for `search_query`, skipping its
+    /// header if one has already been written. The writer is flushed
+    /// afterwards so downstream tools see the rows straight away.
+    pub fn write_response(&mut self, search_query: &str, tsv: &str) -> Result<()> {
+        let mut rows = tsv.lines();
+
+        match rows.next() {
+            Some(header) => {
+                if !self.header_written {
+                    writeln!(self.writer, "{}", header)?;
+                    self.header_written = true;
+                }
+            }
+            // nothing returned for this response
+            None => return Ok(()),
+        }
+
+        for row in rows {
+            if let Some(seen_rows) = &mut self.seen_rows {
+                let seen = seen_rows.entry(search_query.to_string()).or_default();
+                if seen.len() >= self.size || !seen.insert(row.to_string()) {
+                    continue;
+                }
+            }
+            writeln!(self.writer, "{}", row)?;
+        }
+        self.writer.flush()?;
+
+        Ok(())
+    }
+
+    /// Finish writing, erroring if no response had a header.
+    pub fn finish(mut self) -> Result<()> {
+        if !self.header_written {
+            return Err(Error::new(ErrorKind::FormatTSV(
+                "no header found (please report if you get this error!)".to_string(),
+            )));
+        }
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+/// Writes JSON records out as they are made, either as a single
+/// JSON array ([`OutputFormat::Json`]) or one record per line
+/// ([`OutputFormat::Jsonl`]).
+pub struct JsonWriter<W: Write> {
+    writer: W,
+    lines: bool,
+    records_written: usize,
+}
+
+impl<W: Write> JsonWriter<W> {
+    /// Constructor for [`JsonWriter`]. Any format other than
+    /// [`OutputFormat::Jsonl`] writes a JSON array.
+    pub fn new(writer: W, format: OutputFormat) -> Self {
+        Self {
+            writer,
+            lines: format == OutputFormat::Jsonl,
+            records_written: 0,
+        }
+    }
+
+    /// Write a batch of records, flushing afterwards.
+    pub fn write_records(&mut self, records: &[Value]) -> Result<()> {
+        for record in records {
+            let record = serde_json::to_string(record)?;
+            match (self.lines, self.records_written) {
+                (true, _) => writeln!(self.writer, "{}", record)?,
+                (false, 0) => write!(self.writer, "[\n{}", record)?,
+                (false, _) => write!(self.writer, ",\n{}", record)?,
+            }
+            self.records_written += 1;
+        }
+        self.writer.flush()?;
+        Ok(())
+    }
+
+    /// Finish writing, closing the JSON array if needed.
+    pub fn finish(mut self) -> Result<()> {
+        match (self.lines, self.records_written) {
+            (true, _) => (),
+            (false, 0) => writeln!(self.writer, "[]")?,
+            (false, _) => writeln!(self.writer, "\n]")?,
+        }
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+/// Turn a JSON response from the search API into one record per
+/// taxon/assembly.
+///
+/// Each record holds the search query it came from, and everything
+/// under `result` for that hit, so nested fields (e.g. a variable's
+/// `aggregation_source`, or the `:direct`/`:ancestor`/`:descendant`
+/// variants from `--toggle-direct`) are kept as returned.
+pub fn search_records(response: &Value, search_query: &str) -> Result<Vec<Value>> {
+    let results = response["results"].as_array().ok_or_else(|| {
+        Error::new(ErrorKind::GenericCli(format!(
+            "no results found in the search response for {}: {}",
+            search_query, response["status"]
+        )))
+    })?;
+
+    let mut records = Vec::new();
+    for hit in results {
+        let mut record = Map::new();
+        record.insert("search_query".into(), Value::from(search_query));
+        if let Some(result) = hit["result"].as_object() {
+            for (key, value) in result {
+                record.insert(key.clone(), value.clone());
+            }
+        }
+        records.push(Value::Object(record));
+    }
+
+    Ok(records)
+}
+
+/// Drop records already seen for the same search query, for when
+/// the responses of an expression using `OR` are unioned. Records
+/// are keyed on their `assembly_id` or `taxon_id`, and at most
+/// `size` are kept for each search query.
+pub fn unique_records(
+    records: Vec<Value>,
+    seen: &mut HashMap<String, HashSet<String>>,
+    size: usize,
+) -> Vec<Value> {
+    records
+        .into_iter()
+        .filter(|record| {
+            let id = match record.get("assembly_id") {
+                Some(id) => id,
+                None => &record["taxon_id"],
+            };
+            let seen = seen.entry(record["search_query"].to_string()).or_default();
+            seen.len() < size && seen.insert(id.to_string())
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_csv_field_quoting() {
+        assert_eq!(csv_field("Aves"), "Aves");
+        assert_eq!(csv_field("Aves,Mammalia"), "\"Aves,Mammalia\"");
+        assert_eq!(csv_field("a \"b\""), "\"a \"\"b\"\"\"");
+    }
+
+    #[test]
+    fn test_tsv_writer_writes_first_header_only() {
+        let mut out = Vec::new();
+        let mut writer = TsvWriter::new(&mut out);
+        writer
+            .write_response("Aves", "taxon\tcount\nAves\t1\n")
+            .unwrap();
+        writer
+            .write_response("Mammalia", "taxon\tcount\nMammalia\t2")
+            .unwrap();
+        writer.finish().unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "taxon\tcount\nAves\t1\nMammalia\t2\n"
+        );
+    }
+
+    #[test]
+    fn test_tsv_writer_unique_rows() {
+        let mut out = Vec::new();
+        let mut writer = TsvWriter::new(&mut out).unique_rows(10);
+        writer
+            .write_response("Aves", "taxon\tcount\nAves\t1\n")
+            .unwrap();
+        writer
+            .write_response("Aves", "taxon\tcount\nAves\t1\nMammalia\t2")
+            .unwrap();
+        writer.finish().unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "taxon\tcount\nAves\t1\nMammalia\t2\n"
+        );
+    }
+
+    #[test]
+    fn test_tsv_writer_unique_rows_capped_at_size() {
+        let mut out = Vec::new();
+        let mut writer = TsvWriter::new(&mut out).unique_rows(2);
+        writer.write_response("Aves", "taxon_id\n1\n2\n").unwrap();
+        writer.write_response("Aves", "taxon_id\n2\n3\n").unwrap();
+        writer.write_response("Mammalia", "taxon_id\n4\n").unwrap();
+        writer.finish().unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "taxon_id\n1\n2\n4\n");
+    }
+
+    #[test]
+    fn test_unique_records_capped_at_size() {
+        let mut seen = HashMap::new();
+        let records = (1..=3)
+            .map(|id| json!({"search_query": "Aves", "taxon_id": id.to_string()}))
+            .collect();
+        assert_eq!(unique_records(records, &mut seen, 2).len(), 2);
+    }
+
+    #[test]
+    fn test_unique_records_by_search_query_and_id() {
+        let mut seen = HashMap::new();
+        let first = unique_records(
+            vec![json!({"search_query": "Aves", "taxon_id": "1"})],
+            &mut seen,
+            10,
+        );
+        let second = unique_records(
+            vec![
+                json!({"search_query": "Aves", "taxon_id": "1"}),
+                json!({"search_query": "Aves", "taxon_id": "2"}),
+                json!({"search_query": "Mammalia", "taxon_id": "1"}),
+            ],
+            &mut seen,
+            10,
+        );
+        assert_eq!(first.len(), 1);
+        assert_eq!(second.len(), 2);
+    }
+
+    #[test]
+    fn test_tsv_writer_empty_responses_error() {
+        let mut out = Vec::new();
+        let mut writer = TsvWriter::new(&mut out);
+        writer.write_response("Aves", "").unwrap();
+        assert!(writer.finish().is_err());
+    }
+
+    #[test]
+    fn test_json_writer_writes_array() {
+        let mut out = Vec::new();
+        let mut writer = JsonWriter::new(&mut out, OutputFormat::Json);
+        writer.write_records(&[json!({"a": 1})]).unwrap();
+        writer.write_records(&[json!({"a": 2})]).unwrap();
+        writer.finish().unwrap();
+        let parsed: Value = serde_json::from_slice(&out).unwrap();
+        assert_eq!(parsed, json!([{"a": 1}, {"a": 2}]));
+    }
+
+    #[test]
+    fn test_json_writer_no_records_is_empty_array() {
+        let mut out = Vec::new();
+        let writer = JsonWriter::new(&mut out, OutputFormat::Json);
+        writer.finish().unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "[]\n");
+    }
+
+    #[test]
+    fn test_json_writer_writes_lines() {
+        let mut out = Vec::new();
+        let mut writer = JsonWriter::new(&mut out, OutputFormat::Jsonl);
+        writer
+            .write_records(&[json!({"a": 1}), json!({"a": 2})])
+            .unwrap();
+        writer.finish().unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "{\"a\":1}\n{\"a\":2}\n");
+    }
+
+    #[test]
+    fn test_search_records_keeps_nested_fields() {
+        let response = json!({
+            "status": {"hits": 1, "success": true},
+            "results": [{
+                "index": "taxon--ncbi--goat",
+                "id": "9606",
+                "result": {
+                    "taxon_id": "9606",
+                    "scientific_name": "Homo sapiens",
+                    "fields": {
+                        "genome_size": {"value": 3100000000u64, "aggregation_source": "direct"},
+                        "genome_size:direct": {"value": 3100000000u64}
+                    }
+                }
+            }]
+        });
+        let records = search_records(&response, "Homo sapiens").unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0]["search_query"], "Homo sapiens");
+        assert_eq!(records[0]["taxon_id"], "9606");
+        assert_eq!(
+            records[0]["fields"]["genome_size"]["aggregation_source"],
+            "direct"
+        );
+        assert!(records[0]["fields"]["genome_size:direct"].is_object());
+        assert!(records[0].get("index").is_none());
+    }
+
+    #[test]
+    fn test_search_records_missing_results_errors() {
+        let response = json!({"status": {"success": false}});
+        assert!(search_records(&response, "Aves").is_err());
+    }
+}
@@ -0,0 +1,262 @@
+use crate::error::Result;
+use crate::utils::columnar::{as_f64, as_i64, as_text, Column, ColumnSource, ColumnType};
+use crate::IndexType;
+
+use rusqlite::{params, params_from_iter, types::Value as SqlValue, Connection};
+use serde_json::Value;
+use std::collections::HashSet;
+use std::path::Path;
+
+/// Quote a column or table name, as GoaT fields can
+/// contain characters such as `:`.
+fn quote(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+/// The SQLite column type for a [`ColumnType`]. Dates are
+/// stored as ISO 8601 text, and keywords as a JSON array
+/// (query these with `json_each`).
+fn sql_type(column_type: ColumnType) -> &'static str {
+    match column_type {
+        ColumnType::Short | ColumnType::Integer | ColumnType::Long => "INTEGER",
+        ColumnType::Float | ColumnType::HalfFloat => "REAL",
+        ColumnType::Date | ColumnType::Keyword | ColumnType::Text => "TEXT",
+    }
+}
+
+/// Convert the value of a column in a record to a SQLite value.
+fn sql_value(column: &Column, record: &Value) -> SqlValue {
+    let value = column.value(record);
+    let converted = match column.column_type {
+        ColumnType::Short | ColumnType::Integer | ColumnType::Long => {
+            as_i64(value).map(SqlValue::Integer)
+        }
+        ColumnType::Float | ColumnType::HalfFloat => as_f64(value).map(SqlValue::Real),
+        ColumnType::Date => value
+            .as_str()
+            .map(|d| SqlValue::Text(d.get(..10).unwrap_or(d).to_string())),
+        ColumnType::Keyword => match value {
+            Value::Null => None,
+            Value::Array(_) => Some(SqlValue::Text(value.to_string())),
+            keyword => Some(SqlValue::Text(
+                Value::Array(vec![keyword.clone()]).to_string(),
+            )),
+        },
+        ColumnType::Text => as_text(value).map(SqlValue::Text),
+    };
+    converted.unwrap_or(SqlValue::Null)
+}
+
+/// Writes search records into a local SQLite database.
+///
+/// Records go into a table named after the index (`taxon` or
+/// `assembly`), keyed on the taxon or assembly ID. The database
+/// and table are created if needed, and searching again appends
+/// to them: new variables are added as columns, and rows that
+/// already exist are updated. When `--ranks` is used, the names
+/// at each rank go into a `lineage` table instead.
+pub struct SqliteWriter {
+    connection: Connection,
+    table: &'static str,
+    id: &'static str,
+    columns: Vec<Column>,
+    ranks: Vec<Column>,
+    rows_written: usize,
+}
+
+impl SqliteWriter {
+    /// Constructor for [`SqliteWriter`]. Opens (or creates) the
+    /// database at `path`, and makes sure the tables have all of
+    /// the `columns`.
+    pub fn new<P: AsRef<Path>>(
+        path: P,
+        index_type: IndexType,
+        columns: Vec<Column>,
+    ) -> Result<Self> {
+        let connection = Connection::open(path)?;
+        let (table, id) = match index_type {
+            IndexType::Taxon => ("taxon", "taxon_id"),
+            IndexType::Assembly => ("assembly", "assembly_id"),
+        };
+        let (ranks, columns): (Vec<Column>, Vec<Column>) = columns
+            .into_iter()
+            .partition(|c| matches!(c.source, ColumnSource::Rank(_)));
+
+        let definitions = columns
+            .iter()
+            .map(|c| match c.name == id {
+                true => format!("{} {} PRIMARY KEY", quote(&c.name), sql_type(c.column_type)),
+                false => format!("{} {}", quote(&c.name), sql_type(c.column_type)),
+            })
+            .collect::<Vec<String>>()
+            .join(", ");
+        connection.execute_batch(&format!(
+            "CREATE TABLE IF NOT EXISTS {} ({});",
+            quote(table),
+            definitions
+        ))?;
+
+        // an existing table may be missing variables from this search
+        let existing = connection
+            .prepare(&format!("PRAGMA table_info({})", quote(table)))?
+            .query_map([], |row| row.get::<_, String>(1))?
+            .collect::<rusqlite::Result<HashSet<String>>>()?;
+        for column in columns.iter().filter(|c| !existing.contains(&c.name)) {
+            connection.execute_batch(&format!(
+                "ALTER TABLE {} ADD COLUMN {} {};",
+                quote(table),
+                quote(&column.name),
+                sql_type(column.column_type)
+            ))?;
+        }
+
+        if !ranks.is_empty() {
+            connection.execute_batch(
+                "CREATE TABLE IF NOT EXISTS lineage (
+                    taxon_id TEXT NOT NULL,
+                    rank TEXT NOT NULL,
+                    scientific_name TEXT,
+                    PRIMARY KEY (taxon_id, rank)
+                );",
+            )?;
+        }
+
+        Ok(Self {
+            connection,
+            table,
+            id,
+            columns,
+            ranks,
+            rows_written: 0,
+        })
+    }
+
+    /// Write a batch of records in a single transaction.
+    pub fn write_records(&mut self, records: &[Value]) -> Result<()> {
+        let names = self
+            .columns
+            .iter()
+            .map(|c| quote(&c.name))
+            .collect::<Vec<String>>();
+        let placeholders = (1..=names.len())
+            .map(|i| format!("?{}", i))
+            .collect::<Vec<String>>();
+        let updates = names
+            .iter()
+            .map(|name| format!("{name} = excluded.{name}"))
+            .collect::<Vec<String>>();
+        let upsert = format!(
+            "INSERT INTO {} ({}) VALUES ({}) ON CONFLICT({}) DO UPDATE SET {}",
+            quote(self.table),
+            names.join(", "),
+            placeholders.join(", "),
+            quote(self.id),
+            updates.join(", ")
+        );
+
+        let transaction = self.connection.transaction()?;
+        {
+            let mut insert = transaction.prepare_cached(&upsert)?;
+            // the lineage table only exists if `--ranks` was used
+            let mut insert_lineage = match self.ranks.is_empty() {
+                true => None,
+                false => Some(transaction.prepare_cached(
+                    "INSERT OR REPLACE INTO lineage (taxon_id, rank, scientific_name) VALUES (?1, ?2, ?3)",
+                )?),
+            };
+            for record in records {
+                insert.execute(params_from_iter(
+                    self.columns.iter().map(|c| sql_value(c, record)),
+                ))?;
+
+                let (insert_lineage, taxon_id) =
+                    match (&mut insert_lineage, as_text(&record["taxon_id"])) {
+                        (Some(i), Some(t)) => (i, t),
+                        _ => continue,
+                    };
+                for rank in &self.ranks {
+                    if let Some(name) = as_text(rank.value(record)) {
+                        insert_lineage.execute(params![taxon_id, rank.name, name])?;
+                    }
+                }
+            }
+        }
+        transaction.commit()?;
+        self.rows_written += records.len();
+
+        Ok(())
+    }
+
+    /// Finish writing, returning the number of rows written.
+    pub fn finish(self) -> Result<usize> {
+        self.connection.close().map_err(|(_, e)| e)?;
+        Ok(self.rows_written)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::columnar::search_columns;
+    use serde_json::json;
+
+    const URL: &str = "https://goat.genomehubs.org/api/v2/search?query=tax_tree%28Aves%29&ranks=genus&fields=genome_size%2Cassembly_level";
+
+    fn record(taxon_id: &str, genome_size: u64) -> Value {
+        json!({
+            "search_query": "Aves",
+            "taxon_id": taxon_id,
+            "ranks": {"genus": {"scientific_name": "Gallus"}},
+            "fields": {
+                "genome_size": {"value": genome_size},
+                "assembly_level": {"value": "chromosome"}
+            }
+        })
+    }
+
+    #[test]
+    fn test_sqlite_writer_creates_typed_tables_and_upserts() {
+        let path = std::env::temp_dir().join(format!("goat-cli-test-{}.db", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let columns = search_columns(URL, IndexType::Taxon).unwrap();
+        let mut writer = SqliteWriter::new(&path, IndexType::Taxon, columns.clone()).unwrap();
+        writer.write_records(&[record("9031", 1)]).unwrap();
+        assert_eq!(writer.finish().unwrap(), 1);
+
+        // appending updates the existing row, and adds new columns
+        let mut more_columns = columns;
+        more_columns.push(Column {
+            name: "c_value".into(),
+            source: ColumnSource::Field("c_value".into()),
+            column_type: ColumnType::Float,
+        });
+        let mut writer = SqliteWriter::new(&path, IndexType::Taxon, more_columns).unwrap();
+        writer.write_records(&[record("9031", 2)]).unwrap();
+        writer.finish().unwrap();
+
+        let connection = Connection::open(&path).unwrap();
+        let (size, level, size_type): (i64, String, String) = connection
+            .query_row(
+                "SELECT genome_size, assembly_level, typeof(genome_size) FROM taxon",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )
+            .unwrap();
+        assert_eq!(size, 2);
+        assert_eq!(level, "[\"chromosome\"]");
+        assert_eq!(size_type, "integer");
+
+        let genus: String = connection
+            .query_row(
+                "SELECT scientific_name FROM lineage WHERE taxon_id = '9031' AND rank = 'genus'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(genus, "Gallus");
+
+        drop(connection);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -1,10 +1,6 @@
 use crate::error::Result;
 use crate::{
-    utils::{
-        expression::CLIexpression,
-        variable_data::{GOAT_ASSEMBLY_VARIABLE_DATA, GOAT_TAXON_VARIABLE_DATA},
-        variables::Variables,
-    },
+    utils::{catalogue::variable_data, expression::CLIexpression, variables::Variables},
     IndexType,
 };
 use url::Url;
@@ -44,13 +40,17 @@ fn format_names(flag: bool) -> String {
 /// Format an expression put into the `-e` flag on the CLI.
 pub fn format_expression(exp: &str, index_type: IndexType) -> Result<String> {
     let mut new_exp = CLIexpression::new(exp);
-    let parsed_string = match index_type {
-        IndexType::Taxon => new_exp.parse(&GOAT_TAXON_VARIABLE_DATA)?,
-        IndexType::Assembly => new_exp.parse(&GOAT_ASSEMBLY_VARIABLE_DATA)?,
-    };
+    let parsed_string = new_exp.parse(variable_data(index_type))?;
     Ok(parsed_string)
 }
 
+/// Format an expression put into the `-e` flag on the CLI, which
+/// may need more than one query (see [`CLIexpression::parse_branches`]).
+pub fn format_expression_branches(exp: &str, index_type: IndexType) -> Result<Vec<String>> {
+    let mut new_exp = CLIexpression::new(exp);
+    new_exp.parse_branches(variable_data(index_type))
+}
+
 /// Boolean struct containing all of the CLI flag information
 /// passed from the user. This struct has been expanded to include
 /// both `taxon` and `assembly` indexes.
@@ -192,11 +192,11 @@ impl FieldBuilder {
     /// It's a [`Vec`] of a tuple of:
     /// - [`bool`] which shows whether the user chose this flag or not
     /// - [`Vec<&str>`] which enumerates the variable strings (as GoaT
-    /// would recognise) that correspond to this field.
+    ///   would recognise) that correspond to this field.
     ///
     /// It's a bit of a judgement call on my part but happy to change if
     /// there is a compelling argument.
-    fn to_vec_tuples(&self) -> Vec<(bool, Vec<&str>)> {
+    fn to_vec_tuples(self) -> Vec<(bool, Vec<&'static str>)> {
         vec![
             // Add all of the taxon_* fields
             (self.taxon_assembly, vec!["assembly_level", "assembly_span"]),
@@ -357,6 +357,7 @@ fn combine_variable_string(v: String, fb: String) -> String {
 
 /// The function which creats the GoaT API URLs
 /// which are then used as GET requests.
+#[allow(clippy::too_many_arguments)]
 pub fn make_goat_urls(
     api: &str,
     taxids: &[String],
@@ -382,12 +383,7 @@ pub fn make_goat_urls(
 
     // parse the variables, if they have been given.
     let variables_field_string = if let Some(variables) = variables {
-        match index_type {
-            IndexType::Taxon => Variables::new(variables)
-                .parse(&GOAT_TAXON_VARIABLE_DATA, fields.taxon_toggle_direct)?,
-            IndexType::Assembly => Variables::new(variables)
-                .parse(&GOAT_ASSEMBLY_VARIABLE_DATA, fields.taxon_toggle_direct)?,
-        }
+        Variables::new(variables).parse(variable_data(index_type), fields.taxon_toggle_direct)?
     } else {
         "".into()
     };
@@ -401,12 +397,7 @@ pub fn make_goat_urls(
 
     let exclude_pairs: Vec<(String, String)> = if exclude {
         match variables {
-            Some(v) => match index_type {
-                IndexType::Taxon => Variables::new(v).parse_exclude(&GOAT_TAXON_VARIABLE_DATA)?,
-                IndexType::Assembly => {
-                    Variables::new(v).parse_exclude(&GOAT_ASSEMBLY_VARIABLE_DATA)?
-                }
-            },
+            Some(v) => Variables::new(v).parse_exclude(variable_data(index_type))?,
             None => fields.generate_exclude_flags(),
         }
     } else {
@@ -459,3 +450,54 @@ pub fn make_goat_urls(
     }
     Ok(res)
 }
+
+/// Split a search URL into pages of at most `page_size` results, so that
+/// searches larger than a single request allows can be fetched in full.
+///
+/// Each page sets the `size` and `offset` parameters of the API, and is
+/// sorted by the ID of the result (e.g. `taxon_id`), so the pages neither
+/// overlap nor skip results. Pages after the first get their own
+/// `queryId`. `total` is the number of results wanted. If everything fits
+/// in a single page the URL is returned unchanged.
+pub fn paginate_url(url: &str, total: u64, page_size: u64) -> Vec<String> {
+    if total <= page_size {
+        return vec![url.to_string()];
+    }
+
+    let parsed = Url::parse(url).expect("search URLs are valid");
+    let pairs: Vec<(String, String)> = parsed
+        .query_pairs()
+        .filter(|(key, _)| !["offset", "sortBy", "sortOrder"].contains(&key.as_ref()))
+        .map(|(key, value)| (key.into_owned(), value.into_owned()))
+        .collect();
+    let sort_by = match pairs.iter().find(|(key, _)| key == "result") {
+        Some((_, result)) => format!("{}_id", result),
+        None => "taxon_id".to_string(),
+    };
+
+    let mut pages = Vec::new();
+    let mut offset = 0;
+    while offset < total {
+        let size = page_size.min(total - offset);
+        let mut page = parsed.clone();
+        page.query_pairs_mut().clear();
+        for (key, value) in &pairs {
+            match key.as_str() {
+                "size" => page
+                    .query_pairs_mut()
+                    .append_pair("size", &size.to_string())
+                    .append_pair("offset", &offset.to_string())
+                    .append_pair("sortBy", &sort_by)
+                    .append_pair("sortOrder", "asc"),
+                "queryId" if !pages.is_empty() => page
+                    .query_pairs_mut()
+                    .append_pair(key, &format!("{}_{}", value, pages.len())),
+                _ => page.query_pairs_mut().append_pair(key, value),
+            };
+        }
+        pages.push(page.to_string());
+        offset += size;
+    }
+
+    pages
+}
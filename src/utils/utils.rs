@@ -6,12 +6,13 @@ use std::{
 
 use crate::error::{Error, ErrorKind, Result};
 use crate::{
-    utils::expression,
-    utils::variable_data::{GOAT_ASSEMBLY_VARIABLE_DATA, GOAT_TAXON_VARIABLE_DATA},
-    IndexType, UPPER_CLI_FILE_LIMIT,
+    utils::{catalogue, expression},
+    IndexType, GOAT_URL, UPPER_CLI_FILE_LIMIT, UPPER_CLI_REPORT_FILE_LIMIT,
 };
 use rand::distributions::Alphanumeric;
 use rand::{thread_rng, Rng};
+use regex::Regex;
+use url::Url;
 
 pub enum UniqueIdAction {
     Continue(Vec<String>),
@@ -35,12 +36,7 @@ pub fn generate_unique_strings(
 
     if let Some(p) = print_expression {
         if *p {
-            match index_type {
-                IndexType::Taxon => expression::print_variable_data(&GOAT_TAXON_VARIABLE_DATA),
-                IndexType::Assembly => {
-                    expression::print_variable_data(&GOAT_ASSEMBLY_VARIABLE_DATA)
-                }
-            }
+            expression::print_variable_data(catalogue::variable_data(index_type));
             return Ok(UniqueIdAction::PrintedAndExit);
         }
     }
@@ -77,16 +73,104 @@ pub fn generate_unique_strings(
         },
     }
 
-    let url_vector_len = url_vector.len();
+    Ok(UniqueIdAction::Continue(random_ids(url_vector.len())))
+}
 
+/// A vector of `n` random character strings to use as
+/// unique identifiers.
+pub fn random_ids(n: usize) -> Vec<String> {
     let mut chars_vec = vec![];
-    for _ in 0..url_vector_len {
+    for _ in 0..n {
         let mut rng = thread_rng();
         let chars: String = (0..15).map(|_| rng.sample(Alphanumeric) as char).collect();
         chars_vec.push(chars.clone());
     }
+    chars_vec
+}
 
-    Ok(UniqueIdAction::Continue(chars_vec))
+/// The taxa for a report, from `-t` or from each line of the
+/// file given to `-f`.
+///
+/// Reports split large lists into batches, so the file can
+/// have up to [`UPPER_CLI_REPORT_FILE_LIMIT`] entries.
+pub fn report_taxa(matches: &clap::ArgMatches) -> Result<Vec<String>> {
+    let tax_name_op = matches.get_one::<String>("taxon");
+    let filename_op = matches.try_get_one::<PathBuf>("file").ok().flatten();
+
+    let taxa = match (tax_name_op, filename_op) {
+        (Some(s), _) => parse_comma_separated(s),
+        (None, Some(f)) => {
+            let taxa: Vec<String> = lines_from_file(f)?
+                .into_iter()
+                .map(|line| line.trim().to_string())
+                .filter(|line| !line.is_empty())
+                .collect();
+            if taxa.len() > *UPPER_CLI_REPORT_FILE_LIMIT {
+                let limit_string = pretty_print_usize(*UPPER_CLI_REPORT_FILE_LIMIT);
+                return Err(Error::new(ErrorKind::GenericCli(format!(
+                    "Number of taxa specified cannot exceed {}.",
+                    limit_string
+                ))));
+            }
+            taxa
+        }
+        (None, None) => vec![],
+    };
+
+    match taxa.iter().all(|t| t.is_empty()) {
+        true => Err(Error::new(ErrorKind::GenericCli(
+            "One of -f (--file) or -t (--taxon) should be specified.".to_string(),
+        ))),
+        false => Ok(taxa),
+    }
+}
+
+/// Like [`generate_unique_strings`], but for reports, which
+/// need a unique identifier per batch of at most
+/// [`UPPER_CLI_FILE_LIMIT`] taxa.
+pub fn generate_report_ids(matches: &clap::ArgMatches) -> Result<Vec<String>> {
+    let taxa = report_taxa(matches)?;
+    Ok(random_ids(taxa.len().div_ceil(*UPPER_CLI_FILE_LIMIT)))
+}
+
+/// Get the GoaT API URL to build requests against from the CLI.
+///
+/// This is `--api-url` (or the `GOAT_API_URL` environment variable) if
+/// given, otherwise the public [`GOAT_URL`]. A trailing slash is added
+/// if missing, so endpoints can be appended directly.
+pub fn goat_url(matches: &clap::ArgMatches) -> Result<String> {
+    let api_url = match matches.get_one::<String>("api-url") {
+        Some(u) => u.trim().to_string(),
+        None => return Ok(GOAT_URL.clone()),
+    };
+
+    let api_url = match api_url.ends_with('/') {
+        true => api_url,
+        false => format!("{}/", api_url),
+    };
+
+    match Url::parse(&api_url) {
+        Ok(u) if !u.cannot_be_a_base() => Ok(api_url),
+        _ => Err(Error::new(ErrorKind::GenericCli(format!(
+            "could not parse \"{}\" as an API URL, e.g. https://goat.genomehubs.org/api/v2/",
+            api_url
+        )))),
+    }
+}
+
+/// Turn a GoaT API URL into the equivalent GoaT UI URL.
+///
+/// The UI is served from the same host as the API, so the trailing
+/// `api/<version>/` of `goat_url` is removed. URLs not built from
+/// `goat_url` are returned unchanged.
+pub fn goat_ui_url(url: &str, goat_url: &str) -> String {
+    let re = Regex::new(r"api/v\d+/$").unwrap();
+    let ui_base = re.replace(goat_url, "");
+
+    match url.strip_prefix(goat_url) {
+        Some(endpoint) => format!("{}{}", ui_base, endpoint),
+        None => url.to_string(),
+    }
 }
 
 /// Read NCBI taxon ID's or binomial names of species,
@@ -132,55 +216,6 @@ pub fn get_rank_vector(r: &str) -> Vec<String> {
     }
 }
 
-/// If multiple taxa are queried at once, headers will return for every new taxon.
-/// We can suppress this by storing the whole return as a string.
-pub fn format_tsv_output(awaited_fetches: Vec<Result<String>>) -> Result<()> {
-    // if there is a single element, return this.
-    // is there a way to get all the headers, and compare them...
-    let mut headers = Vec::new();
-    for el in &awaited_fetches {
-        let tsv = match el {
-            Ok(ref e) => e,
-            Err(e) => return Err(Error::new(ErrorKind::FormatTSV(e.to_string()))),
-        };
-        headers.push(tsv.split('\n').next());
-    }
-
-    // mainly a guard - but Rich I think fixed this so shouldn't need to be done.
-    let header = headers.iter().fold(headers[0], |acc, &item| {
-        let acc = acc?;
-        let item = item?;
-        if item.len() > acc.len() {
-            Some(item)
-        } else {
-            Some(acc)
-        }
-    });
-
-    match header {
-        Some(h) => println!("{}", h),
-        None => {
-            return Err(Error::new(ErrorKind::FormatTSV(
-                "no header found (please report if you get this error!)".to_string(),
-            )))
-        }
-    }
-
-    for el in awaited_fetches {
-        let tsv = match el {
-            Ok(ref e) => e,
-            Err(e) => return Err(e),
-        };
-
-        let tsv_iter = tsv.split('\n');
-        for row in tsv_iter.skip(1) {
-            println!("{}", row)
-        }
-    }
-
-    Ok(())
-}
-
 /// Thanks to [this](https://stackoverflow.com/questions/38406793/why-is-capitalizing-the-first-letter-of-a-string-so-convoluted-in-rust)
 /// post on stack overflow. Make a string uppercase on the first character.
 pub fn some_kind_of_uppercase_first_letter(s: &str) -> String {
@@ -318,7 +353,7 @@ fn levenshtein_distance(a: &str, b: &str) -> usize {
 
 #[cfg(test)]
 mod tests {
-    use super::{parse_comma_separated, pretty_print_usize};
+    use super::{goat_ui_url, parse_comma_separated, pretty_print_usize};
     #[test]
     fn test_parse_comma_separated_trims_and_preserves_order() {
         let parsed = parse_comma_separated(" Mammalia, Aves ,Reptilia ");
@@ -367,4 +402,28 @@ mod tests {
     fn test_pretty_print_usize_mid_range() {
         assert_eq!(pretty_print_usize(12345), "12,345");
     }
+
+    #[test]
+    fn test_goat_ui_url_public_instance() {
+        let url = goat_ui_url(
+            "https://goat.genomehubs.org/api/v2/search?query=tax_name%28Aves%29",
+            "https://goat.genomehubs.org/api/v2/",
+        );
+        assert_eq!(
+            url,
+            "https://goat.genomehubs.org/search?query=tax_name%28Aves%29"
+        );
+    }
+
+    #[test]
+    fn test_goat_ui_url_follows_configured_host() {
+        let url = goat_ui_url(
+            "http://localhost:8080/genomehubs/api/v3/search?query=tax_name%28Aves%29",
+            "http://localhost:8080/genomehubs/api/v3/",
+        );
+        assert_eq!(
+            url,
+            "http://localhost:8080/genomehubs/search?query=tax_name%28Aves%29"
+        );
+    }
 }
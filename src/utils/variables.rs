@@ -28,8 +28,8 @@ impl<'a> Variables<'a> {
         let variable = self.variables;
 
         let var_vec_check = reference_data
-            .iter()
-            .map(|(e, _)| e.to_string())
+            .keys()
+            .map(|e| e.to_string())
             .collect::<Vec<String>>();
 
         if !var_vec_check.contains(&variable.to_string()) {
@@ -59,8 +59,8 @@ impl<'a> Variables<'a> {
         let split_vec = parse_comma_separated(self.variables);
         // check that all the strings in split_vec are real
         let var_vec_check = reference_data
-            .iter()
-            .map(|(e, _)| e.to_string())
+            .keys()
+            .map(|e| e.to_string())
             .collect::<Vec<String>>();
 
         for variable in &split_vec {
@@ -99,8 +99,8 @@ impl<'a> Variables<'a> {
         let split_vec = parse_comma_separated(self.variables);
         // check that all the strings in split_vec are real
         let var_vec_check = reference_data
-            .iter()
-            .map(|(e, _)| e.to_string())
+            .keys()
+            .map(|e| e.to_string())
             .collect::<Vec<String>>();
 
         for variable in &split_vec {
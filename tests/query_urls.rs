@@ -1,4 +1,6 @@
-use goat_cli::utils::url::{format_expression, make_goat_urls, FieldBuilder};
+use goat_cli::utils::url::{
+    format_expression, format_expression_branches, make_goat_urls, paginate_url, FieldBuilder,
+};
 use goat_cli::IndexType;
 
 // ── helpers ──────────────────────────────────────────────────────────────────
@@ -89,6 +91,18 @@ fn test_format_expression_rejects_tax_rank_in_expression() {
     assert!(result.is_err());
 }
 
+#[test]
+fn test_format_expression_branches_for_or() {
+    let branches = format_expression_branches(
+        "genome_size > 1000 OR contig_n50 > 1000000",
+        IndexType::Taxon,
+    )
+    .expect("expression parsed");
+    assert_eq!(branches.len(), 2);
+    assert!(branches[0].contains("genome_size"));
+    assert!(branches[1].contains("contig_n50"));
+}
+
 // ── make_goat_urls: core shape ────────────────────────────────────────────────
 
 #[test]
@@ -396,3 +410,80 @@ fn test_invalid_variable_returns_err() {
     );
     assert!(result.is_err());
 }
+
+// ── paginate_url ─────────────────────────────────────────────────────────────
+
+#[test]
+fn test_paginate_url_single_page_is_unchanged() {
+    let urls = make_taxon_urls(
+        &[String::from("Mammalia")],
+        empty_fields(),
+        "",
+        vec![String::from("id1")],
+    );
+    let pages = paginate_url(&urls[0], 50, 50000);
+    assert_eq!(pages, vec![urls[0].clone()]);
+}
+
+#[test]
+fn test_paginate_url_splits_into_offset_pages() {
+    let urls = make_taxon_urls(
+        &[String::from("Mammalia")],
+        empty_fields(),
+        "",
+        vec![String::from("id1")],
+    );
+    let pages = paginate_url(&urls[0], 120000, 50000);
+
+    assert_eq!(pages.len(), 3);
+    assert!(pages[0].contains("size=50000&offset=0&"));
+    assert!(pages[1].contains("size=50000&offset=50000&"));
+    assert!(pages[2].contains("size=20000&offset=100000&"));
+    // each page is tracked on its own
+    assert!(pages[0].ends_with("queryId=goat_cli_id1"));
+    assert!(pages[1].ends_with("queryId=goat_cli_id1_1"));
+    assert!(pages[2].ends_with("queryId=goat_cli_id1_2"));
+    // the rest of the query is untouched
+    for page in &pages {
+        assert!(page.contains("query=tax_name%28Mammalia%29"));
+        assert!(!page.contains("size=50&"));
+    }
+}
+
+#[test]
+fn test_paginate_url_pages_are_sorted_by_id() {
+    let urls = make_taxon_urls(
+        &[String::from("Mammalia")],
+        empty_fields(),
+        "",
+        vec![String::from("id1")],
+    );
+    for page in paginate_url(&urls[0], 120000, 50000) {
+        assert!(page.contains("&sortBy=taxon_id&sortOrder=asc"));
+    }
+
+    let urls = make_goat_urls(
+        "search",
+        &[String::from("Mammalia")],
+        "https://goat.genomehubs.org/api/v2/",
+        "tree",
+        false,
+        false,
+        false,
+        "count",
+        "assembly",
+        "ncbi",
+        50,
+        "none",
+        empty_fields(),
+        None,
+        "",
+        "",
+        vec![String::from("id1")],
+        IndexType::Assembly,
+    )
+    .expect("URL generation should not fail");
+    for page in paginate_url(&urls[0], 120000, 50000) {
+        assert!(page.contains("&sortBy=assembly_id&sortOrder=asc"));
+    }
+}